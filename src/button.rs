@@ -2,17 +2,22 @@ use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::Style,
-    text::Line,
+    text::{Span, Text},
     widgets::{Block, Borders, Widget},
 };
 
-use crate::util::styles::Styles;
+use crate::util::styles::Theme;
 
 #[derive(Debug, Clone)]
 pub struct Button<'a> {
-    label: Line<'a>,
+    label: Text<'a>,
+    /// Glyph drawn before the first line of the label
+    icon: Option<&'a str>,
     pub state: State,
     focused: bool,
+    /// Draw the focused border in its brighter shade, toggled to animate a pulse
+    pulse: bool,
+    theme: Theme,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,11 +27,15 @@ pub enum State {
 }
 
 impl<'a> Button<'a> {
-    pub fn new<T: Into<Line<'a>>>(label: T) -> Self {
+    /// A button labelled with one or more lines, e.g. `"Apply\n[Enter]"`
+    pub fn new<T: Into<Text<'a>>>(label: T) -> Self {
         Self {
             label: label.into(),
+            icon: None,
             state: State::Normal,
             focused: false,
+            pulse: false,
+            theme: Theme::default(),
         }
     }
 
@@ -39,26 +48,55 @@ impl<'a> Button<'a> {
         self.focused = focused;
         self
     }
+
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub fn pulse(mut self, pulse: bool) -> Self {
+        self.pulse = pulse;
+        self
+    }
+
+    pub fn icon(mut self, icon: &'a str) -> Self {
+        self.icon = Some(icon);
+        self
+    }
 }
 
 impl Widget for Button<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let (bg, fg) = Styles::button_colors(self.focused);
+        let (bg, fg) = self.theme.button_colors(self.focused);
 
-        // Use cyan border when focused
-        let border_style = Styles::focus_border(self.focused);
+        // Use the focus color when focused, a brighter one on the bright half of a pulse
+        let border_style = if self.focused && self.pulse {
+            self.theme.pulse_border()
+        } else {
+            self.theme.focus_border(self.focused)
+        };
 
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(border_style)
             .style(Style::default().bg(bg).fg(fg));
 
+        let inner = block.inner(area);
         block.render(area, buf);
 
-        let label_width = self.label.width() as u16;
-        let x = area.x + (area.width.saturating_sub(label_width)) / 2;
-        let y = area.y + area.height / 2;
+        let mut lines = self.label.lines;
+        if let (Some(icon), Some(first)) = (self.icon, lines.first_mut()) {
+            first.spans.insert(0, Span::raw(format!("{icon} ")));
+        }
+
+        // Center the block of lines vertically, then each line on its own
+        let height = (lines.len() as u16).min(inner.height);
+        let top = inner.y + (inner.height - height) / 2;
 
-        buf.set_line(x, y, &self.label, area.width);
+        for (i, line) in lines.iter().take(height as usize).enumerate() {
+            let width = line.width() as u16;
+            let x = inner.x + inner.width.saturating_sub(width) / 2;
+            buf.set_line(x, top + i as u16, line, inner.width);
+        }
     }
 }
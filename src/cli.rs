@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use color_eyre::{
+    Result,
+    eyre::{bail, eyre},
+};
+use color_picker_ratatui::{
+    output::{Gamut, OutputFormat},
+    util::styles::Theme,
+};
+
+/// Command-line options for the standalone picker
+#[derive(Debug, Default)]
+pub struct Args {
+    pub format: OutputFormat,
+    /// Custom property to declare with `--format css`, e.g. `brand` for `--brand: ...;`
+    pub css_name: Option<String>,
+    /// Color space of the terminal, `srgb` or `p3`; output is sRGB either way
+    pub gamut: Gamut,
+    /// Render inline instead of on the alternate screen
+    pub no_alt_screen: bool,
+    /// File to load the palette from instead of the material colors
+    pub palette: Option<PathBuf>,
+    /// Image to take the dominant colors from as the palette
+    pub image: Option<PathBuf>,
+    /// Pick from the xterm 256-color palette instead
+    pub ansi_256: bool,
+    /// Print the palette in the output format and exit without opening the picker
+    pub dump_palette: bool,
+    /// File or FIFO to write the color to instead of stdout
+    pub output: Option<PathBuf>,
+    /// Fixed swatch width in columns, for squarer swatches
+    pub swatch_width: Option<u16>,
+    /// Draw each swatch's hex code on it
+    pub labels: bool,
+    /// Render in truecolor even if the terminal doesn't advertise it
+    pub force_truecolor: bool,
+    /// Keep the UI still instead of pulsing the focused button
+    pub no_animations: bool,
+    /// Keep the input cursor solid instead of blinking
+    pub no_blink: bool,
+    /// Show the palette as a single row, for short terminals
+    pub compact: bool,
+    /// Colors for the modal chrome, `dark` or `light`
+    pub theme: Theme,
+    /// Open with the input focused, for typing a color straight away
+    pub focus_input: bool,
+    /// Move to Apply as soon as a full hex color is typed
+    pub auto_advance: bool,
+    /// Color to start on, snapped to the nearest palette swatch
+    pub color: Option<String>,
+}
+
+impl Args {
+    /// Parse `std::env::args`, accepting both `--flag value` and `--flag=value`
+    pub fn parse() -> Result<Self> {
+        let mut args = Self::default();
+        let mut raw = std::env::args().skip(1);
+
+        while let Some(arg) = raw.next() {
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| raw.next())
+                    .ok_or_else(|| eyre!("`{flag}` expects a value"))
+            };
+
+            match flag.as_str() {
+                "--format" => args.format = value()?.parse()?,
+                "--css-name" => args.css_name = Some(value()?),
+                "--gamut" => args.gamut = value()?.parse()?,
+                "--no-alt-screen" => args.no_alt_screen = true,
+                "--palette" => args.palette = Some(value()?.into()),
+                "--image" => args.image = Some(value()?.into()),
+                "--ansi-256" => args.ansi_256 = true,
+                "--dump-palette" => args.dump_palette = true,
+                "--output" => args.output = Some(value()?.into()),
+                "--swatch-width" => args.swatch_width = Some(value()?.parse()?),
+                "--labels" => args.labels = true,
+                "--force-truecolor" => args.force_truecolor = true,
+                "--no-animations" => args.no_animations = true,
+                "--no-blink" => args.no_blink = true,
+                "--compact" => args.compact = true,
+                "--focus-input" => args.focus_input = true,
+                "--auto-advance" => args.auto_advance = true,
+                "--color" => args.color = Some(value()?),
+                "--theme" => args.theme = value()?.parse()?,
+                _ => bail!("unknown argument `{flag}`"),
+            }
+        }
+
+        Ok(args)
+    }
+}
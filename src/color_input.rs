@@ -1,9 +1,112 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::style::Color;
+
+use crate::util::{
+    clipboard,
+    color_support::approximate_rgb,
+    convert::{rgb_to_cmyk, rgb_to_hex_bare, rgb_to_hsl},
+    named_colors::{CSS_COLORS, LONGEST_NAME},
+    parse::{parse_cmyk, parse_color, parse_hsl, parse_rgb},
+};
 
 #[derive(Debug, Default, Clone)]
 pub struct ColorInput {
     pub input: String,
     pub cursor_pos: usize,
+    pub mode: InputMode,
+    undo_stack: Vec<Snapshot>,
+    redo_stack: Vec<Snapshot>,
+}
+
+/// Input text and cursor position before an edit
+type Snapshot = (String, usize);
+
+/// Maximum number of edits that can be undone
+const HISTORY_LIMIT: usize = 64;
+
+/// How far the input is from a usable color
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+    /// Nothing typed yet
+    Empty,
+    /// Not a color yet, but typing more could make it one, e.g. `FF8` on the
+    /// way to `FF8800` or `lavend` on the way to `lavender`
+    Incomplete,
+    /// A complete color
+    Valid,
+    /// No amount of further typing makes this a color
+    Invalid,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    #[default]
+    Hex,
+    Rgb,
+    Hsl,
+    Cmyk,
+}
+
+impl InputMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Hex => Self::Rgb,
+            Self::Rgb => Self::Hsl,
+            Self::Hsl => Self::Cmyk,
+            Self::Cmyk => Self::Hex,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Hex => "HEX",
+            Self::Rgb => "RGB",
+            Self::Hsl => "HSL",
+            Self::Cmyk => "CMYK",
+        }
+    }
+
+    pub fn placeholder(self) -> &'static str {
+        match self {
+            Self::Hex => "#______",
+            Self::Rgb => "R,G,B",
+            Self::Hsl => "H,S,L",
+            Self::Cmyk => "C,M,Y,K",
+        }
+    }
+
+    /// Longest input accepted, e.g. `255, 255, 255` for RGB
+    fn max_len(self) -> usize {
+        match self {
+            Self::Hex => 8,
+            Self::Rgb => 13,
+            Self::Hsl => 11,
+            Self::Cmyk => 15,
+        }
+    }
+
+    /// Number of comma-separated components and the largest value each may
+    /// take, for the decimal modes. HSL clamps out-of-range values instead.
+    fn components(self) -> Option<(usize, u64)> {
+        match self {
+            Self::Hex => None,
+            Self::Rgb => Some((3, 255)),
+            Self::Hsl => Some((3, u64::MAX)),
+            Self::Cmyk => Some((4, 100)),
+        }
+    }
+
+    /// Characters that can make up a valid input; anything else is still typed
+    /// but flagged as invalid
+    pub fn accepts(self, c: char) -> bool {
+        match self {
+            // Letters beyond `f` spell out a CSS color name, and the rest an
+            // `rgb(r, g, b)` call
+            Self::Hex => c.is_ascii_alphanumeric() || matches!(c, '#' | '(' | ')' | ',' | ' '),
+            Self::Rgb => c.is_ascii_digit() || c == ',' || c == ' ',
+            Self::Hsl | Self::Cmyk => c.is_ascii_digit() || c == ',',
+        }
+    }
 }
 
 impl ColorInput {
@@ -12,27 +115,428 @@ impl ColorInput {
             return;
         }
 
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
         match key.code {
-            KeyCode::Char(c) if c.is_ascii_hexdigit() && self.input.len() < 6 => {
-                let c = c.to_ascii_uppercase();
+            KeyCode::Char('v') if ctrl => {
+                if let Some(text) = clipboard::get_text() {
+                    self.paste(&text);
+                }
+            }
+            KeyCode::Char('z') if ctrl => self.undo(),
+            KeyCode::Char('y') if ctrl => self.redo(),
+            // Other Ctrl chords aren't text, so Ctrl+A doesn't type an `a`
+            KeyCode::Char(_) if ctrl => {}
+            KeyCode::Char(c) if (c.is_ascii_graphic() || c == ' ') && self.has_room_for(c) => {
+                self.record();
                 self.input.insert(self.cursor_pos, c);
+                self.normalize_case();
                 self.cursor_pos += 1;
             }
             KeyCode::Backspace if self.cursor_pos > 0 => {
+                self.record();
                 self.input.remove(self.cursor_pos - 1);
                 self.cursor_pos -= 1;
             }
+            KeyCode::Left if ctrl => self.cursor_pos = self.prev_boundary(),
+            KeyCode::Right if ctrl => self.cursor_pos = self.next_boundary(),
             KeyCode::Left => self.cursor_pos = self.cursor_pos.saturating_sub(1),
             KeyCode::Delete if self.cursor_pos < self.input.len() => {
+                self.record();
                 self.input.remove(self.cursor_pos);
             }
             KeyCode::Home => self.cursor_pos = 0,
+            KeyCode::End => self.cursor_pos = self.input.len(),
             KeyCode::Right => self.cursor_pos = (self.cursor_pos + 1).min(self.input.len()),
             _ => {}
         }
     }
 
+    /// Start of the component before the cursor, or of the previous one when
+    /// already there. Hex has no separators, so this is the start of the input.
+    fn prev_boundary(&self) -> usize {
+        let before = &self.input[..self.cursor_pos];
+        let before = before.strip_suffix(',').unwrap_or(before);
+        before.rfind(',').map_or(0, |i| i + 1)
+    }
+
+    /// End of the component after the cursor, or of the next one when already
+    /// there. Hex has no separators, so this is the end of the input.
+    fn next_boundary(&self) -> usize {
+        let skip = usize::from(self.input[self.cursor_pos..].starts_with(','));
+        let start = self.cursor_pos + skip;
+        self.input[start..]
+            .find(',')
+            .map_or(self.input.len(), |i| start + i)
+    }
+
+    /// Replace the input with a pasted color in one step. The trimmed text must
+    /// be a whole color: exactly 3, 6 or 8 hex digits after an optional `#`, or
+    /// anything else [`parse_color`] takes, like `rgb(1, 2, 3)` or `teal`. Words
+    /// that merely start with hex digits, like `accent`, don't count.
+    /// Returns false and leaves the input untouched otherwise.
+    pub fn paste(&mut self, text: &str) -> bool {
+        let text = text.trim();
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        let is_hex = matches!(hex.len(), 3 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+
+        if is_hex && self.mode == InputMode::Hex {
+            self.record();
+            self.input = hex.to_ascii_uppercase();
+            self.cursor_pos = self.input.len();
+            return true;
+        }
+
+        match parse_color(text) {
+            Some((color, _)) => {
+                self.record();
+                self.set_color(color);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// True when typing `c` in hex mode would spell more of a CSS color name,
+    /// so letters bound to shortcuts like `m` can still be typed in one
+    pub fn continues_name(&self, c: char) -> bool {
+        if self.mode != InputMode::Hex {
+            return false;
+        }
+
+        let mut prefix = self.input.to_ascii_lowercase();
+        prefix.insert(self.cursor_pos, c.to_ascii_lowercase());
+        CSS_COLORS.iter().any(|(name, _)| name.starts_with(&prefix))
+    }
+
+    /// Hex input stops at 8 digits, but a color name may run longer
+    fn has_room_for(&self, c: char) -> bool {
+        let hex = self.mode == InputMode::Hex
+            && c.is_ascii_hexdigit()
+            && self.input.chars().all(|c| c.is_ascii_hexdigit());
+
+        let max_len = if self.mode == InputMode::Hex && !hex {
+            LONGEST_NAME
+        } else {
+            self.mode.max_len()
+        };
+        self.input.len() < max_len
+    }
+
+    /// Uppercase hex digits, but show anything else as a lowercase color name.
+    /// Invalid characters don't count either way.
+    fn normalize_case(&mut self) {
+        let hex = self
+            .input
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .all(|c| c.is_ascii_hexdigit());
+
+        if hex {
+            self.input.make_ascii_uppercase();
+        } else {
+            self.input.make_ascii_lowercase();
+        }
+    }
+
+    /// Remember the current state before an edit, dropping the redo history
+    fn record(&mut self) {
+        if self.undo_stack.len() == HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push((self.input.clone(), self.cursor_pos));
+        self.redo_stack.clear();
+    }
+
+    /// Empty the input in one step that can be undone
+    pub fn clear(&mut self) {
+        if self.input.is_empty() {
+            return;
+        }
+        self.record();
+        self.input.clear();
+        self.cursor_pos = 0;
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(snapshot) = self.undo_stack.pop() {
+            let current = std::mem::replace(&mut self.input, snapshot.0);
+            self.redo_stack.push((current, self.cursor_pos));
+            self.cursor_pos = snapshot.1;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(snapshot) = self.redo_stack.pop() {
+            let current = std::mem::replace(&mut self.input, snapshot.0);
+            self.undo_stack.push((current, self.cursor_pos));
+            self.cursor_pos = snapshot.1;
+        }
+    }
+
+    /// True only when the input is a complete color for the active mode,
+    /// including a known CSS color name in hex mode
     pub fn is_valid(&self) -> bool {
-        self.input.len() == 6 && self.input.chars().all(|c| c.is_ascii_hexdigit())
+        self.color().is_some()
+    }
+
+    /// Whether the input is empty, a color, on its way to one, or hopeless
+    pub fn validity(&self) -> Validity {
+        if self.input.is_empty() {
+            Validity::Empty
+        } else if self.is_valid() {
+            Validity::Valid
+        } else if self.has_valid_chars() && self.could_complete() {
+            Validity::Incomplete
+        } else {
+            Validity::Invalid
+        }
+    }
+
+    /// True when typing more could turn the input into a color
+    fn could_complete(&self) -> bool {
+        let Some((count, max)) = self.mode.components() else {
+            // Hex stops at 8 digits; anything else has to be spelling a name
+            // or an `rgb(...)` call
+            let hex = self.input.strip_prefix('#').unwrap_or(&self.input);
+            if hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return hex.len() < 8;
+            }
+            let prefix = self.input.to_ascii_lowercase();
+            if let Some(args) = prefix.strip_prefix("rgb") {
+                let args = args.trim_start();
+                return args.is_empty()
+                    || args
+                        .strip_prefix('(')
+                        .is_some_and(|args| components_could_complete(args, 3, 255));
+            }
+            return CSS_COLORS.iter().any(|(name, _)| name.starts_with(&prefix));
+        };
+
+        components_could_complete(&self.input, count, max)
+    }
+
+    /// Digits typed out of those needed for a hex color, e.g. `(3, 6)`. The
+    /// target becomes 8 past six digits, for alpha. `None` outside hex mode or
+    /// while spelling a name.
+    pub fn hex_progress(&self) -> Option<(usize, usize)> {
+        if self.mode != InputMode::Hex || !self.input.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        let len = self.input.len();
+        Some((len, if len > 6 { 8 } else { 6 }))
+    }
+
+    /// True when every character typed so far could be part of a valid input
+    pub fn has_valid_chars(&self) -> bool {
+        self.input.chars().all(|c| self.mode.accepts(c))
+    }
+
+    /// Parse the current input according to the active mode. Hex mode takes
+    /// anything [`parse_color`] does.
+    pub fn color(&self) -> Option<Color> {
+        match self.mode {
+            InputMode::Hex => parse_color(&self.input).map(|(color, _)| color),
+            _ if !self.has_valid_chars() => None,
+            InputMode::Rgb => parse_rgb(&self.input),
+            InputMode::Hsl => parse_hsl(&self.input),
+            InputMode::Cmyk => parse_cmyk(&self.input),
+        }
+    }
+
+    /// Alpha channel of an 8-digit hex input (`RRGGBBAA`)
+    pub fn alpha(&self) -> Option<u8> {
+        match self.mode {
+            InputMode::Hex => parse_color(&self.input).and_then(|(_, alpha)| alpha),
+            _ => None,
+        }
+    }
+
+    /// Replace the input with `color` formatted for the active mode. The input
+    /// only holds RGB, so indexed and named colors are shown as their usual
+    /// xterm values (`Indexed(208)` as `FF8700`). `Reset` has no known value
+    /// and leaves the input as it is.
+    pub fn set_color(&mut self, color: Color) {
+        let Some((r, g, b)) = approximate_rgb(color) else {
+            return;
+        };
+
+        self.input = match self.mode {
+            InputMode::Hex => rgb_to_hex_bare(r, g, b),
+            InputMode::Rgb => format!("{r},{g},{b}"),
+            InputMode::Hsl => {
+                let (h, s, l) = rgb_to_hsl(r, g, b);
+                format!("{h},{s},{l}")
+            }
+            InputMode::Cmyk => {
+                let (c, m, y, k) = rgb_to_cmyk(r, g, b);
+                format!("{c},{m},{y},{k}")
+            }
+        };
+        self.cursor_pos = self.input.len();
+    }
+
+    /// Switch to the next mode, carrying a valid color over to the new format
+    pub fn cycle_mode(&mut self) {
+        let color = self.color();
+        self.mode = self.mode.next();
+
+        // Snapshots from the previous mode would restore text in the wrong format
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+
+        match color {
+            Some(color) => self.set_color(color),
+            None => {
+                self.input.clear();
+                self.cursor_pos = 0;
+            }
+        }
+    }
+}
+
+/// True when `input` could still grow into `count` comma-separated components
+/// of at most `max`. Components still being typed may be empty, but none may
+/// overflow.
+fn components_could_complete(input: &str, count: usize, max: u64) -> bool {
+    let parts: Vec<_> = input.split(',').map(str::trim).collect();
+    parts.len() <= count
+        && parts
+            .iter()
+            .all(|part| part.is_empty() || part.parse::<u64>().is_ok_and(|v| v <= max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str, cursor_pos: usize, mode: InputMode) -> ColorInput {
+        ColorInput {
+            input: text.to_string(),
+            cursor_pos,
+            mode,
+            ..Default::default()
+        }
+    }
+
+    fn ctrl(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn paste_takes_whole_colors() {
+        let cases = [
+            ("#FF8800", "FF8800"),
+            ("ff8800ff", "FF8800FF"),
+            ("  #abc\n", "ABC"),
+            ("rgb(1, 2, 3)", "010203"),
+            ("teal", "008080"),
+        ];
+
+        for (text, expected) in cases {
+            let mut input = ColorInput::default();
+            assert!(input.paste(text), "{text:?}");
+            assert_eq!(input.input, expected, "{text:?}");
+            assert_eq!(input.cursor_pos, expected.len(), "{text:?}");
+        }
+
+        let mut rgb = input("", 0, InputMode::Rgb);
+        assert!(rgb.paste("#FF8800"));
+        assert_eq!(rgb.input, "255,136,0");
+    }
+
+    #[test]
+    fn paste_rejects_partial_colors_and_words() {
+        for text in [
+            "accent",
+            "added",
+            "face",
+            "#ff88",
+            "#ff8800;",
+            "ff8800ff00",
+            "",
+            "  ",
+        ] {
+            let mut input = ColorInput::default();
+            input.paste("123456");
+
+            assert!(!input.paste(text), "{text:?}");
+            assert_eq!(input.input, "123456", "{text:?}");
+            assert_eq!(input.cursor_pos, 6, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn unbound_ctrl_chords_type_nothing() {
+        let mut typed = input("FF", 2, InputMode::Hex);
+        for c in ['a', 'b', 'e', 'x', 'k'] {
+            typed.handle_key_event(ctrl(KeyCode::Char(c)));
+        }
+        assert_eq!(typed.input, "FF");
+        assert_eq!(typed.cursor_pos, 2);
+
+        // The bound ones still work
+        typed.handle_key_event(KeyEvent::from(KeyCode::Char('a')));
+        assert_eq!(typed.input, "FFA");
+        typed.handle_key_event(ctrl(KeyCode::Char('z')));
+        assert_eq!(typed.input, "FF");
+        typed.handle_key_event(ctrl(KeyCode::Char('y')));
+        assert_eq!(typed.input, "FFA");
+    }
+
+    /// Cursor positions after each of `presses` of `code` with Ctrl held
+    fn word_jumps(mut input: ColorInput, code: KeyCode, presses: usize) -> Vec<usize> {
+        (0..presses)
+            .map(|_| {
+                input.handle_key_event(ctrl(code));
+                input.cursor_pos
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ctrl_left_stops_at_each_component_start() {
+        let rgb = input("12,34,56", 8, InputMode::Rgb);
+        assert_eq!(word_jumps(rgb, KeyCode::Left, 4), [6, 3, 0, 0]);
+
+        // From inside a component, first to its own start
+        let rgb = input("12,34,56", 4, InputMode::Rgb);
+        assert_eq!(word_jumps(rgb, KeyCode::Left, 2), [3, 0]);
+
+        let cmyk = input("0,50,100,25", 11, InputMode::Cmyk);
+        assert_eq!(word_jumps(cmyk, KeyCode::Left, 4), [9, 5, 2, 0]);
+    }
+
+    #[test]
+    fn ctrl_right_stops_at_each_component_end() {
+        let rgb = input("12,34,56", 0, InputMode::Rgb);
+        assert_eq!(word_jumps(rgb, KeyCode::Right, 4), [2, 5, 8, 8]);
+
+        // From inside a component, first to its own end
+        let rgb = input("12,34,56", 4, InputMode::Rgb);
+        assert_eq!(word_jumps(rgb, KeyCode::Right, 2), [5, 8]);
+
+        let cmyk = input("0,50,100,25", 0, InputMode::Cmyk);
+        assert_eq!(word_jumps(cmyk, KeyCode::Right, 4), [1, 4, 8, 11]);
+    }
+
+    #[test]
+    fn ctrl_arrows_jump_across_hex_and_empty_components() {
+        let hex = input("FF8800", 3, InputMode::Hex);
+        assert_eq!(word_jumps(hex.clone(), KeyCode::Left, 1), [0]);
+        assert_eq!(word_jumps(hex, KeyCode::Right, 1), [6]);
+
+        let partial = input("12,,56", 6, InputMode::Rgb);
+        assert_eq!(word_jumps(partial.clone(), KeyCode::Left, 3), [4, 3, 0]);
+        let partial = ColorInput {
+            cursor_pos: 0,
+            ..partial
+        };
+        assert_eq!(word_jumps(partial, KeyCode::Right, 3), [2, 3, 6]);
+
+        let empty = input("", 0, InputMode::Rgb);
+        assert_eq!(word_jumps(empty.clone(), KeyCode::Left, 1), [0]);
+        assert_eq!(word_jumps(empty, KeyCode::Right, 1), [0]);
     }
 }
@@ -0,0 +1,138 @@
+use std::{fs, io, path::PathBuf};
+
+use color_eyre::{Result, eyre::WrapErr};
+use directories::ProjectDirs;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keymap::KeyMap, modal::ColorPickerWidget, palette::PaletteOrder, util::parse::parse_color,
+};
+
+/// State persisted between runs, stored as TOML in the user's config directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Recently applied colors as `#RRGGBB`, or `ansi:N` for ANSI palette
+    /// colors, most recent first
+    pub recent: Vec<String>,
+    /// Pinned colors in the same form as `recent`, in the order they were pinned
+    pub favorites: Vec<String>,
+    pub last_color: Option<String>,
+    /// Open the modal focused on the input rather than the grid
+    pub start_on_input: bool,
+    /// `shade-rows` (the default) or `hue-rows` for the built-in hue palettes
+    pub palette_order: PaletteOrder,
+    /// Modal width and height as percentages of the terminal
+    pub modal_size: Option<(u16, u16)>,
+    /// Columns and rows the modal was moved from the center
+    pub modal_offset: (i16, i16),
+    /// Shortcut overrides, any action left out keeps its default key
+    pub keys: KeyMap,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "color-picker-ratatui")
+            .map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    /// Load the config, or the defaults if there isn't one yet. Errs if the
+    /// file can't be read or doesn't parse.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => {
+                return Err(err).wrap_err_with(|| format!("Couldn't read `{}`", path.display()));
+            }
+        };
+        toml::from_str(&contents).wrap_err_with(|| format!("Couldn't parse `{}`", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Recent colors that parse, skipping anything malformed
+    pub fn recent_colors(&self) -> Vec<Color> {
+        parse_all(&self.recent)
+    }
+
+    /// Favorite colors that parse, skipping anything malformed
+    pub fn favorite_colors(&self) -> Vec<Color> {
+        parse_all(&self.favorites)
+    }
+}
+
+/// Prefix of an ANSI palette color, which has no exact hex, e.g. `ansi:208`
+const ANSI_PREFIX: &str = "ansi:";
+
+fn parse_all(entries: &[String]) -> Vec<Color> {
+    entries
+        .iter()
+        .filter_map(|entry| from_entry(entry))
+        .collect()
+}
+
+/// A color as stored in the config: `ansi:N` or anything [`parse_color`] takes
+fn from_entry(entry: &str) -> Option<Color> {
+    match entry.strip_prefix(ANSI_PREFIX) {
+        Some(index) => index.parse().ok().map(Color::Indexed),
+        None => parse_color(entry).map(|(color, _)| color),
+    }
+}
+
+/// `#RRGGBB` for RGB colors and `ansi:N` for ANSI palette colors. Named
+/// colors aren't stored.
+pub fn to_entry(color: Color) -> Option<String> {
+    match ColorPickerWidget::color_to_ansi_index(color) {
+        Some(index) => Some(format!("{ANSI_PREFIX}{index}")),
+        None => ColorPickerWidget::color_to_hex(color).map(|hex| format!("#{hex}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_and_rgb_colors_survive_a_save() {
+        let colors = [
+            Color::Rgb(0xFF, 0x88, 0x00),
+            Color::Indexed(1),
+            Color::Indexed(208),
+        ];
+        let config = Config {
+            recent: colors.iter().copied().filter_map(to_entry).collect(),
+            ..Default::default()
+        };
+        assert_eq!(config.recent, ["#FF8800", "ansi:1", "ansi:208"]);
+
+        let saved: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(saved.recent_colors(), colors);
+    }
+
+    #[test]
+    fn malformed_entries_are_skipped() {
+        let config = Config {
+            favorites: ["ansi:256", "ansi:", "ansi:x", "#ff88", "teal"]
+                .map(String::from)
+                .to_vec(),
+            ..Default::default()
+        };
+        assert_eq!(config.favorite_colors(), [Color::Rgb(0x00, 0x80, 0x80)]);
+    }
+}
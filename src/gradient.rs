@@ -0,0 +1,145 @@
+use ratatui::style::Color;
+
+use crate::util::{
+    color_support::approximate_rgb,
+    convert::{mix_linear, mix_rgb},
+};
+
+/// Fewest and most stops a gradient can have, including both ends
+pub const MIN_STEPS: usize = 2;
+pub const MAX_STEPS: usize = 16;
+
+/// A multi-stop gradient between two marked colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gradient {
+    pub start: Option<Color>,
+    pub end: Option<Color>,
+    /// Number of stops, including both ends
+    pub steps: usize,
+    /// Blend the sRGB channels directly instead of in linear light
+    pub naive: bool,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            start: None,
+            end: None,
+            steps: 5,
+            naive: false,
+        }
+    }
+}
+
+impl Gradient {
+    /// Mark `color` as the start, then the end. Marking a complete gradient
+    /// starts a new one. Returns a status message describing what was marked.
+    pub fn mark(&mut self, color: Color) -> &'static str {
+        match (self.start, self.end) {
+            (Some(_), None) => {
+                self.end = Some(color);
+                "Gradient end marked"
+            }
+            _ => {
+                self.start = Some(color);
+                self.end = None;
+                "Gradient start marked"
+            }
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.start.is_some() && self.end.is_some()
+    }
+
+    pub fn adjust_steps(&mut self, delta: isize) {
+        self.steps = self
+            .steps
+            .saturating_add_signed(delta)
+            .clamp(MIN_STEPS, MAX_STEPS);
+    }
+
+    /// Evenly spaced stops from start to end, or nothing until both ends are
+    /// marked. Indexed and named ends blend from their usual xterm values.
+    pub fn stops(&self) -> Vec<Color> {
+        let (Some(start), Some(end)) = (
+            self.start.and_then(approximate_rgb),
+            self.end.and_then(approximate_rgb),
+        ) else {
+            return Vec::new();
+        };
+
+        let last = (self.steps - 1) as f64;
+        (0..self.steps)
+            .map(|i| {
+                let t = i as f64 / last;
+                let (r, g, b) = if self.naive {
+                    mix_rgb(start, end, t)
+                } else {
+                    mix_linear(start, end, t)
+                };
+                Color::Rgb(r, g, b)
+            })
+            .collect()
+    }
+
+    /// Strip title, e.g. `Gradient · linear · 5 stops`
+    pub fn label(&self) -> String {
+        let blend = if self.naive { "sRGB" } else { "linear" };
+        format!("Gradient · {blend} · {} stops", self.steps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(start: Color, end: Color, steps: usize) -> Gradient {
+        Gradient {
+            start: Some(start),
+            end: Some(end),
+            steps,
+            naive: true,
+        }
+    }
+
+    #[test]
+    fn stops_run_from_start_to_end() {
+        let stops = gradient(Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255), 3).stops();
+        assert_eq!(
+            stops,
+            [
+                Color::Rgb(0, 0, 0),
+                Color::Rgb(128, 128, 128),
+                Color::Rgb(255, 255, 255)
+            ]
+        );
+    }
+
+    #[test]
+    fn indexed_ends_blend_from_their_rgb_values() {
+        // ANSI 16 black and white, 0 and 15
+        let stops = gradient(Color::Indexed(0), Color::Indexed(15), 3).stops();
+        assert_eq!(stops.len(), 3);
+        assert_eq!(stops[0], Color::Rgb(0, 0, 0));
+        assert_eq!(stops[2], Color::Rgb(255, 255, 255));
+
+        for naive in [true, false] {
+            let mixed = Gradient {
+                naive,
+                ..gradient(Color::Indexed(196), Color::Rgb(0, 0, 255), 4)
+            };
+            assert_eq!(mixed.stops().len(), 4);
+        }
+    }
+
+    #[test]
+    fn no_stops_until_both_ends_are_marked() {
+        let mut gradient = Gradient::default();
+        assert!(gradient.stops().is_empty());
+        gradient.mark(Color::Indexed(1));
+        assert!(gradient.stops().is_empty());
+        gradient.mark(Color::Indexed(4));
+        assert_eq!(gradient.stops().len(), gradient.steps);
+    }
+}
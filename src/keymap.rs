@@ -0,0 +1,194 @@
+use std::{fmt, str::FromStr};
+
+use color_eyre::eyre::{Report, eyre};
+use ratatui::crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+/// A single key binding, written in the config as `"p"`, `"tab"`, `"f2"` and so on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Key(pub KeyCode);
+
+impl Key {
+    /// Letters match in either case, so Shift and Caps Lock don't break a binding
+    pub fn matches(self, code: KeyCode) -> bool {
+        match (self.0, code) {
+            (KeyCode::Char(a), KeyCode::Char(b)) => a.eq_ignore_ascii_case(&b),
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Self(KeyCode::Char(c)));
+        }
+
+        let code = match s.to_ascii_lowercase().as_str() {
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "enter" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "delete" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(eyre!("unknown key `{s}`")),
+            },
+        };
+
+        Ok(Self(code))
+    }
+}
+
+impl TryFrom<String> for Key {
+    type Error = Report;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+impl From<Key> for String {
+    fn from(key: Key) -> Self {
+        key.to_string()
+    }
+}
+
+/// Keys bound to each action. Esc always quits and the arrow keys always
+/// navigate, so a bad config can't lock anyone in.
+///
+/// Letters that are hex digits (`a`-`f`) still reach the input while it has
+/// focus, so only bind them to actions that are ignored there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyMap {
+    pub quit: Key,
+    pub toggle_modal: Key,
+    pub copy: Key,
+    pub focus_next: Key,
+    pub focus_prev: Key,
+    /// Activates the focused button or samples with the eyedropper
+    pub confirm: Key,
+    pub cycle_input_mode: Key,
+    pub toggle_sliders: Key,
+    pub cycle_vision: Key,
+    pub toggle_eyedropper: Key,
+    pub toggle_lock: Key,
+    pub search: Key,
+    /// Pin the current color to the favorites, or unpin it
+    pub toggle_favorite: Key,
+    /// Jump to a random palette swatch
+    pub random_swatch: Key,
+    /// Enter a fully random RGB color
+    pub random_color: Key,
+    /// Switch to the next built-in palette
+    pub cycle_palette: Key,
+    /// Mark the gradient start, then its end
+    pub mark_gradient: Key,
+    pub more_gradient_stops: Key,
+    pub fewer_gradient_stops: Key,
+    /// Switch the gradient between linear-light and sRGB blending
+    pub toggle_gradient_blend: Key,
+    /// Show or hide the hex code on each swatch
+    pub toggle_labels: Key,
+    /// Replace the current color with its RGB inverse
+    pub invert: Key,
+    /// Replace the current color with its complementary hue
+    pub complement: Key,
+    /// Separate the grid swatches with gaps, or draw them seamlessly
+    pub toggle_gaps: Key,
+    /// Raise or lower the lightness of the whole palette
+    pub lighten: Key,
+    pub darken: Key,
+    /// Add the current color to the selection, or take it out again. Apply
+    /// writes out every selected color.
+    pub toggle_selection: Key,
+    /// Show or hide the HSL gauges for fine-tuning alongside the grid
+    pub toggle_hsl: Key,
+    /// Show each color's weight as a bar instead of the grid, for palettes
+    /// taken from an image
+    pub toggle_histogram: Key,
+    /// Copy the SGR escape sequence for the current color
+    pub copy_escape: Key,
+    /// Switch the escape sequence between foreground and background
+    pub toggle_escape_layer: Key,
+    /// Fill the grid with a color scheme built on the current color, then
+    /// step through the kinds of scheme
+    pub cycle_harmony: Key,
+    /// Lock the active slider or HSL gauge so the arrows can't change it,
+    /// for keeping e.g. the hue fixed while building a ramp
+    pub lock_channel: Key,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            quit: Key(KeyCode::Char('q')),
+            toggle_modal: Key(KeyCode::Char('p')),
+            copy: Key(KeyCode::Char('c')),
+            focus_next: Key(KeyCode::Tab),
+            focus_prev: Key(KeyCode::BackTab),
+            confirm: Key(KeyCode::Enter),
+            cycle_input_mode: Key(KeyCode::Char('m')),
+            toggle_sliders: Key(KeyCode::Char('s')),
+            cycle_vision: Key(KeyCode::Char('v')),
+            toggle_eyedropper: Key(KeyCode::Char('i')),
+            toggle_lock: Key(KeyCode::Char('l')),
+            search: Key(KeyCode::Char('/')),
+            toggle_favorite: Key(KeyCode::Char('*')),
+            random_swatch: Key(KeyCode::Char('r')),
+            random_color: Key(KeyCode::Char('x')),
+            cycle_palette: Key(KeyCode::Char('t')),
+            mark_gradient: Key(KeyCode::Char('g')),
+            more_gradient_stops: Key(KeyCode::Char('+')),
+            fewer_gradient_stops: Key(KeyCode::Char('-')),
+            toggle_gradient_blend: Key(KeyCode::Char('n')),
+            toggle_labels: Key(KeyCode::Char('h')),
+            invert: Key(KeyCode::Char('~')),
+            complement: Key(KeyCode::Char('k')),
+            toggle_gaps: Key(KeyCode::Char('o')),
+            lighten: Key(KeyCode::Char(']')),
+            darken: Key(KeyCode::Char('[')),
+            toggle_selection: Key(KeyCode::Char(' ')),
+            toggle_hsl: Key(KeyCode::Char('u')),
+            toggle_histogram: Key(KeyCode::Char('w')),
+            copy_escape: Key(KeyCode::Char('y')),
+            toggle_escape_layer: Key(KeyCode::Char('j')),
+            cycle_harmony: Key(KeyCode::Char('z')),
+            lock_channel: Key(KeyCode::Char('f')),
+        }
+    }
+}
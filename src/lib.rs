@@ -0,0 +1,1546 @@
+use color_eyre::Result;
+use crossterm::{
+    cursor::Show,
+    event::{
+        self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    },
+    execute,
+    terminal::{
+        self, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    },
+};
+use ratatui::{
+    Terminal, TerminalOptions, Viewport,
+    backend::Backend,
+    crossterm::event::{
+        KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
+    layout::{Position, Rect},
+    prelude::CrosstermBackend,
+    style::Color,
+};
+use std::{
+    io::{self, BufWriter, IsTerminal, Stderr, Write, stderr},
+    panic,
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+pub use crate::{
+    color_input::{ColorInput, InputMode, Validity},
+    modal::{ColorPickerWidget, Focus},
+    // The color math works without a terminal, for use as a plain library
+    util::convert,
+};
+use crate::{
+    config::Config,
+    keymap::KeyMap,
+    modal::{FAVORITES_CAPACITY, LIGHTNESS_STEP},
+    output::OutputFormat,
+    palette::{Palette, PaletteOrder},
+    util::{
+        clipboard,
+        color_support::{ColorSupport, approximate_rgb},
+        styles::Theme,
+    },
+};
+
+mod button;
+pub mod color_input;
+pub mod config;
+pub mod gradient;
+pub mod keymap;
+pub mod modal;
+pub mod output;
+pub mod palette;
+mod sliders;
+pub mod util {
+    pub mod clipboard;
+    pub mod color_support;
+    pub mod contrast;
+    pub mod convert;
+    pub mod named_colors;
+    pub mod parse;
+    pub mod styles;
+    pub mod vision;
+}
+
+#[derive(Debug, Default)]
+pub struct Model {
+    color_picker: ColorPickerWidget,
+    applied_color: Option<Color>,
+    output_format: OutputFormat,
+    /// Custom property name for CSS output, without the leading `--`
+    css_name: Option<String>,
+    selected_output: Option<String>,
+    /// Receives every applied color, useful while the modal is locked open
+    apply_sender: Option<Sender<Color>>,
+    outcome: Outcome,
+}
+
+/// Why the picker stopped running
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Closed with Apply on this color
+    Applied(Color),
+    /// Closed with Cancel, or by discarding edited input
+    Cancelled,
+    /// Quit without applying or cancelling
+    #[default]
+    Quit,
+}
+
+impl Model {
+    pub fn output_format(mut self, output_format: OutputFormat) -> Self {
+        self.output_format = output_format;
+        self
+    }
+
+    /// Declare the custom property `--{name}` in CSS output instead of `color`
+    pub fn css_name(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.css_name = Some(name.trim_start_matches('-').to_string());
+        self
+    }
+
+    /// Send each applied color through `sender` as it happens
+    pub fn apply_sender(mut self, sender: Sender<Color>) -> Self {
+        self.apply_sender = Some(sender);
+        self
+    }
+
+    /// Rebind the shortcut keys, e.g. to move `p` on a Dvorak layout
+    pub fn keymap(mut self, keymap: KeyMap) -> Self {
+        self.color_picker.keymap = keymap;
+        self
+    }
+
+    /// Draw swatches `width` columns wide and half as tall, instead of
+    /// stretching them to fill the grid
+    pub fn swatch_width(mut self, width: u16) -> Self {
+        self.color_picker.swatch_width = Some(width.max(1));
+        self
+    }
+
+    /// Render with no more colors than the terminal supports; output keeps the
+    /// true color either way. See [`ColorSupport::detect`].
+    pub fn color_support(mut self, support: ColorSupport) -> Self {
+        self.color_picker.color_support = support;
+        self
+    }
+
+    /// Leave a gap between grid swatches instead of drawing them seamlessly
+    pub fn grid_gaps(mut self, gaps: bool) -> Self {
+        self.color_picker.grid_gaps = gaps;
+        self
+    }
+
+    /// Always show the palette as a single row; it's used anyway on short terminals
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.color_picker.compact = compact;
+        self
+    }
+
+    /// Colors for the modal chrome, e.g. [`Theme::light`] on light terminals
+    pub fn theme(mut self, theme: Theme) -> Self {
+        self.color_picker.theme = theme;
+        self
+    }
+
+    /// Pulse the focused button; pass `false` to keep the UI still
+    pub fn animations(mut self, enabled: bool) -> Self {
+        self.color_picker.animations = enabled;
+        self
+    }
+
+    /// Blink the input cursor; it stays solid anyway when animations are off
+    pub fn blink(mut self, enabled: bool) -> Self {
+        self.color_picker.blink = enabled;
+        self
+    }
+
+    /// Jump from the input to Apply once a complete hex color is typed
+    pub fn auto_advance(mut self, enabled: bool) -> Self {
+        self.color_picker.auto_advance = enabled;
+        self
+    }
+
+    /// Focus the input whenever the modal opens, prefilled with the selected swatch
+    pub fn start_on_input(mut self, enabled: bool) -> Self {
+        self.color_picker.start_on_input = enabled;
+        self
+    }
+
+    /// Draw each swatch's hex code on it, where it fits
+    pub fn show_labels(mut self, show: bool) -> Self {
+        self.color_picker.show_labels = show;
+        self
+    }
+
+    /// Use a custom palette instead of the built-in material colors
+    pub fn palette(mut self, colors: Vec<Color>) -> Self {
+        self.color_picker.set_palette(colors);
+        self
+    }
+
+    /// Lay out the material and tailwind palettes with one hue per row instead
+    /// of one per column
+    pub fn palette_order(mut self, order: PaletteOrder) -> Self {
+        self.color_picker.set_palette_order(order);
+        self
+    }
+
+    /// Use a custom palette where each color carries a weight, such as its share
+    /// of an image, which the histogram view draws as bars
+    pub fn weighted_palette(mut self, colors: Vec<(Color, f64)>) -> Self {
+        self.color_picker.set_weighted_palette(colors);
+        self
+    }
+
+    /// Start on one of the built-in palettes instead of material
+    pub fn builtin_palette(mut self, palette: Palette) -> Self {
+        self.color_picker.set_builtin_palette(palette);
+        self
+    }
+
+    /// Use a custom palette laid out as `(rows, cols)`
+    pub fn palette_grid(mut self, colors: Vec<Color>, dimensions: (usize, usize)) -> Self {
+        self.color_picker.set_palette_grid(colors, dimensions);
+        self
+    }
+
+    /// Open on the palette swatch closest to `color`; call after choosing the palette
+    pub fn initial_color(mut self, color: Color) -> Self {
+        self.color_picker.select_nearest(color);
+        self
+    }
+
+    /// The color currently shown, see [`ColorPickerWidget::current_color`]
+    pub fn current_color(&self) -> Option<Color> {
+        self.color_picker.current_color()
+    }
+
+    /// Every palette color in the output format, one per line (an array for JSON)
+    pub fn palette_output(&self) -> String {
+        let colors = &self.color_picker.colors;
+        self.output_format
+            .format_list(colors, self.css_name.as_deref())
+    }
+
+    /// How the picker was closed, once it has stopped running
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+
+    /// The applied color rendered in the chosen output format
+    pub fn output(&self) -> Option<&str> {
+        self.selected_output.as_deref()
+    }
+
+    /// The color confirmed with Apply, if any
+    pub fn applied_color(&self) -> Option<Color> {
+        self.applied_color
+    }
+
+    /// Build a model with the recent colors, favorites and keymap restored from `config`
+    pub fn from_config(config: &Config) -> Self {
+        let mut model = Self::default()
+            .keymap(config.keys)
+            .start_on_input(config.start_on_input)
+            .palette_order(config.palette_order);
+        let picker = &mut model.color_picker;
+
+        for color in config.recent_colors().into_iter().rev() {
+            picker.push_recent(color);
+        }
+        picker.favorites = config.favorite_colors();
+        picker.favorites.truncate(FAVORITES_CAPACITY);
+
+        // A hand-edited size could be anything; the offset is pulled back
+        // on-screen on the first render, whatever the terminal size
+        if let Some((width, height)) = config.modal_size {
+            picker.modal_size = (width.clamp(1, 100), height.clamp(1, 100));
+        }
+        picker.modal_offset.set(config.modal_offset);
+
+        model
+    }
+
+    /// Copy the state that should survive between runs into `config`
+    pub fn update_config(&self, config: &mut Config) {
+        let recent = &self.color_picker.recent;
+        config.recent = recent
+            .iter()
+            .copied()
+            .filter_map(config::to_entry)
+            .collect();
+        let favorites = &self.color_picker.favorites;
+        config.favorites = favorites
+            .iter()
+            .copied()
+            .filter_map(config::to_entry)
+            .collect();
+
+        if let Some(entry) = self.applied_color.and_then(config::to_entry) {
+            config.last_color = Some(entry);
+        }
+        config.modal_size = Some(self.color_picker.modal_size);
+        config.modal_offset = self.color_picker.modal_offset.get();
+    }
+
+    /// Alpha of the applied color, when an 8-digit hex was entered
+    pub fn applied_alpha(&self) -> Option<u8> {
+        self.color_picker.alpha
+    }
+}
+
+#[derive(Debug)]
+pub enum Message {
+    KeyPress(KeyEvent),
+    Mouse(MouseEvent),
+    Paste(String),
+    ToggleModal,
+    /// Shift the modal by columns and rows
+    MoveModal(i16, i16),
+    ApplyColor,
+    /// Enter in the input: accept a valid color and move on to Apply
+    CommitInput,
+    /// Clear the input and return the grid cursor to the first swatch
+    Reset,
+    UpdateColorFromGrid,
+    UpdateColorFromRecent,
+    UpdateColorFromFavorites,
+    ToggleFavorite,
+    ToggleSelection,
+    /// Answer the discard prompt: `true` cancels, `false` keeps editing
+    ConfirmDiscard(bool),
+    RandomSwatch,
+    CyclePalette,
+    CycleHarmony,
+    RandomColor,
+    UpdateColorFromShades,
+    UpdateColorFromSliders,
+    ToggleSliders,
+    UpdateColorFromHsl,
+    ToggleHsl,
+    ToggleHistogram,
+    CycleVisionMode,
+    ToggleLock,
+    ToggleLabels,
+    ToggleGaps,
+    /// Step the selected RGB channel of the current color
+    Nudge(i16),
+    Invert,
+    Complement,
+    /// Select the next (1) or previous (-1) RGB channel to nudge
+    CycleNudgeChannel(isize),
+    /// Shift the palette lightness by this many percentage points
+    AdjustLightness(i8),
+    ToggleEyedropper,
+    SampleEyedropper,
+    StartSearch,
+    MarkGradient,
+    AdjustGradientSteps(isize),
+    ToggleGradientBlend,
+    ClearSearch,
+    CycleInputMode,
+    CopyColor,
+    CopyEscape,
+    ToggleEscapeLayer,
+    CancelColorSelection,
+    FocusNext,
+    FocusPrev,
+    Quit,
+    /// No input arrived within [`TICK_RATE`]; lets timed state like toasts update
+    Tick,
+    Ignore,
+}
+
+/// How often the UI redraws while idle
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+// Centralized key mapping
+struct KeyHandler;
+
+impl KeyHandler {
+    fn handle_global_keys(model: &Model, key: KeyEvent) -> Option<Message> {
+        let picker = &model.color_picker;
+        let keys = &model.color_picker.keymap;
+
+        // Letters may be part of a color name, so only Esc quits while typing
+        let typing = picker.modal_state && picker.focus == Focus::Input;
+
+        match key.code {
+            KeyCode::Esc if picker.modal_state => Some(Message::CancelColorSelection),
+            KeyCode::Esc => Some(Message::Quit),
+            // Alt keeps the arrows free for navigation within the modal
+            code @ (KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right)
+                if picker.modal_state && key.modifiers.contains(KeyModifiers::ALT) =>
+            {
+                let (dx, dy) = match code {
+                    KeyCode::Up => (0, -1),
+                    KeyCode::Down => (0, 1),
+                    KeyCode::Left => (-2, 0),
+                    _ => (2, 0),
+                };
+                Some(Message::MoveModal(dx, dy))
+            }
+            // Ctrl+R anywhere, or Delete once the input is already empty
+            KeyCode::Char('r' | 'R')
+                if picker.modal_state && key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Some(Message::Reset)
+            }
+            KeyCode::Delete if typing && picker.color_input.input.is_empty() => {
+                Some(Message::Reset)
+            }
+            KeyCode::Char(_) if typing => None,
+            code if keys.quit.matches(code) => Some(Message::Quit),
+            code if keys.toggle_modal.matches(code) => Some(Message::ToggleModal),
+            code if keys.copy.matches(code) && picker.modal_state => Some(Message::CopyColor),
+            _ => None,
+        }
+    }
+
+    fn handle_modal_navigation(model: &mut Model, key: KeyEvent) -> Option<Message> {
+        match model.color_picker.focus {
+            Focus::Grid | Focus::Search => match key.code {
+                KeyCode::Up
+                | KeyCode::Down
+                | KeyCode::Left
+                | KeyCode::Right
+                | KeyCode::Home
+                | KeyCode::End
+                | KeyCode::PageUp
+                | KeyCode::PageDown => {
+                    Self::update_grid_position(model, key.code);
+                    Some(Message::UpdateColorFromGrid)
+                }
+                KeyCode::Char(c) if !model.color_picker.histogram_active() => {
+                    let col = hue_column(c)?;
+                    let picker = &mut model.color_picker;
+                    if col >= picker.grid_dimensions.1 {
+                        return None;
+                    }
+                    picker.grid_index.1 = col;
+                    Some(Message::UpdateColorFromGrid)
+                }
+                _ => None,
+            },
+            Focus::Sliders => {
+                let lock = model.color_picker.keymap.lock_channel;
+                let sliders = &mut model.color_picker.sliders;
+                let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    10
+                } else {
+                    1
+                };
+
+                match key.code {
+                    KeyCode::Up => sliders.select_prev(),
+                    KeyCode::Down => sliders.select_next(),
+                    KeyCode::Left => sliders.step(-step),
+                    KeyCode::Right => sliders.step(step),
+                    code if lock.matches(code) => sliders.locks.toggle(sliders.active),
+                    _ => return None,
+                }
+                Some(Message::UpdateColorFromSliders)
+            }
+            Focus::Hsl => {
+                let picker = &mut model.color_picker;
+                let lock = picker.keymap.lock_channel;
+                picker.sync_hsl();
+                let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    10
+                } else {
+                    1
+                };
+
+                match key.code {
+                    KeyCode::Up => picker.hsl.select_prev(),
+                    KeyCode::Down => picker.hsl.select_next(),
+                    KeyCode::Left => picker.hsl.step(-step),
+                    KeyCode::Right => picker.hsl.step(step),
+                    code if lock.matches(code) => picker.hsl.locks.toggle(picker.hsl.active),
+                    _ => return None,
+                }
+                Some(Message::UpdateColorFromHsl)
+            }
+            Focus::Shades => match key.code {
+                KeyCode::Left | KeyCode::Right => {
+                    let picker = &mut model.color_picker;
+                    let len = picker.shade_strip().len();
+                    picker.shade_index = Self::strip_position(picker.shade_index, len, key.code);
+                    Some(Message::UpdateColorFromShades)
+                }
+                _ => None,
+            },
+            Focus::Eyedropper => {
+                let (dx, dy) = match key.code {
+                    KeyCode::Up => (0, -1),
+                    KeyCode::Down => (0, 1),
+                    KeyCode::Left => (-1, 0),
+                    KeyCode::Right => (1, 0),
+                    _ => return None,
+                };
+                model.color_picker.move_eyedropper(dx, dy);
+                Some(Message::Ignore)
+            }
+            Focus::Recent => match key.code {
+                KeyCode::Left | KeyCode::Right => {
+                    let picker = &mut model.color_picker;
+                    let len = picker.recent.len();
+                    picker.recent_index = Self::strip_position(picker.recent_index, len, key.code);
+                    Some(Message::UpdateColorFromRecent)
+                }
+                _ => None,
+            },
+            Focus::Favorites => match key.code {
+                KeyCode::Left | KeyCode::Right => {
+                    let picker = &mut model.color_picker;
+                    let len = picker.favorites.len();
+                    picker.favorite_index =
+                        Self::strip_position(picker.favorite_index, len, key.code);
+                    Some(Message::UpdateColorFromFavorites)
+                }
+                _ => None,
+            },
+            // The buttons sit side by side, Apply on the left
+            Focus::Apply | Focus::Cancel => {
+                model.color_picker.focus = match key.code {
+                    KeyCode::Left => Focus::Apply,
+                    KeyCode::Right => Focus::Cancel,
+                    _ => return None,
+                };
+                Some(Message::Ignore)
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_modal_actions(model: &Model, key: KeyEvent) -> Option<Message> {
+        let keys = &model.color_picker.keymap;
+        let focus = model.color_picker.focus;
+        let typing = focus == Focus::Input;
+
+        match key.code {
+            code if keys.focus_next.matches(code) => Some(Message::FocusNext),
+            code if keys.focus_prev.matches(code) => Some(Message::FocusPrev),
+            code if keys.confirm.matches(code) => match focus {
+                Focus::Grid | Focus::Apply => Some(Message::ApplyColor),
+                Focus::Input => Some(Message::CommitInput),
+                Focus::Cancel => Some(Message::CancelColorSelection),
+                Focus::Eyedropper => Some(Message::SampleEyedropper),
+                _ => None,
+            },
+            KeyCode::Esc => Some(Message::CancelColorSelection),
+            // Let `m` and `k` through when they're spelling out a name like `magenta`
+            KeyCode::Char(c) if typing && model.color_picker.color_input.continues_name(c) => None,
+            code if typing && keys.cycle_input_mode.matches(code) => Some(Message::CycleInputMode),
+            code if typing && keys.invert.matches(code) => Some(Message::Invert),
+            code if typing && keys.complement.matches(code) => Some(Message::Complement),
+            // The remaining shortcuts may be hex digits or other typed characters
+            _ if typing => None,
+            code if keys.toggle_sliders.matches(code) => Some(Message::ToggleSliders),
+            code if keys.toggle_hsl.matches(code) => Some(Message::ToggleHsl),
+            code if keys.toggle_histogram.matches(code) => Some(Message::ToggleHistogram),
+            code if keys.copy_escape.matches(code) => Some(Message::CopyEscape),
+            code if keys.toggle_escape_layer.matches(code) => Some(Message::ToggleEscapeLayer),
+            code if keys.cycle_vision.matches(code) => Some(Message::CycleVisionMode),
+            code if keys.toggle_eyedropper.matches(code) => Some(Message::ToggleEyedropper),
+            code if keys.toggle_lock.matches(code) => Some(Message::ToggleLock),
+            code if keys.toggle_labels.matches(code) => Some(Message::ToggleLabels),
+            code if keys.toggle_gaps.matches(code) => Some(Message::ToggleGaps),
+            code if keys.invert.matches(code) => Some(Message::Invert),
+            code if keys.complement.matches(code) => Some(Message::Complement),
+            code if keys.lighten.matches(code) => Some(Message::AdjustLightness(LIGHTNESS_STEP)),
+            code if keys.darken.matches(code) => Some(Message::AdjustLightness(-LIGHTNESS_STEP)),
+            code if keys.search.matches(code) => Some(Message::StartSearch),
+            code if keys.toggle_favorite.matches(code) => Some(Message::ToggleFavorite),
+            code if keys.toggle_selection.matches(code) => Some(Message::ToggleSelection),
+            code if keys.random_swatch.matches(code) => Some(Message::RandomSwatch),
+            code if keys.cycle_palette.matches(code) => Some(Message::CyclePalette),
+            code if keys.cycle_harmony.matches(code) => Some(Message::CycleHarmony),
+            code if keys.random_color.matches(code) => Some(Message::RandomColor),
+            code if keys.mark_gradient.matches(code) => Some(Message::MarkGradient),
+            code if keys.more_gradient_stops.matches(code) => Some(Message::AdjustGradientSteps(1)),
+            code if keys.fewer_gradient_stops.matches(code) => {
+                Some(Message::AdjustGradientSteps(-1))
+            }
+            code if keys.toggle_gradient_blend.matches(code) => Some(Message::ToggleGradientBlend),
+            _ => None,
+        }
+    }
+
+    /// Shift+Left/Right picks an RGB channel and Shift+Up/Down nudges it by one.
+    /// The sliders and HSL gauges keep Shift for their larger steps.
+    fn handle_nudge_keys(model: &Model, key: KeyEvent) -> Option<Message> {
+        if !key.modifiers.contains(KeyModifiers::SHIFT)
+            || matches!(model.color_picker.focus, Focus::Sliders | Focus::Hsl)
+        {
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Up => Some(Message::Nudge(1)),
+            KeyCode::Down => Some(Message::Nudge(-1)),
+            KeyCode::Left => Some(Message::CycleNudgeChannel(-1)),
+            KeyCode::Right => Some(Message::CycleNudgeChannel(1)),
+            _ => None,
+        }
+    }
+
+    /// While the discard prompt is up, only y and n (or Esc) do anything
+    fn handle_confirm_keys(key: KeyEvent) -> Message {
+        match key.code {
+            KeyCode::Char('y' | 'Y') => Message::ConfirmDiscard(true),
+            KeyCode::Char('n' | 'N') | KeyCode::Esc => Message::ConfirmDiscard(false),
+            _ => Message::Ignore,
+        }
+    }
+
+    /// Edit the search query. Runs before the global keys so letters like `q`
+    /// and `p` can be typed into it.
+    fn handle_search_keys(model: &mut Model, key: KeyEvent) -> Option<Message> {
+        let picker = &mut model.color_picker;
+
+        match key.code {
+            KeyCode::Esc => return Some(Message::ClearSearch),
+            KeyCode::Enter => {
+                picker.focus = Focus::Grid;
+                return Some(Message::Ignore);
+            }
+            KeyCode::Char(c) if c.is_ascii_alphabetic() => {
+                picker.search.push(c.to_ascii_lowercase());
+            }
+            KeyCode::Backspace => {
+                picker.search.pop();
+            }
+            _ => return None,
+        }
+
+        if picker.snap_to_search() {
+            Some(Message::UpdateColorFromGrid)
+        } else {
+            Some(Message::Ignore)
+        }
+    }
+
+    fn handle_input_keys(model: &mut Model, key: KeyEvent) -> bool {
+        let picker = &mut model.color_picker;
+
+        // Backspace undoes an auto-advance, going back to edit the input
+        if picker.auto_advance && picker.focus == Focus::Apply && key.code == KeyCode::Backspace {
+            picker.focus = Focus::Input;
+        }
+
+        if picker.focus == Focus::Input {
+            let before = picker.color_input.input.clone();
+            let original = picker.original.or_else(|| picker.current_color());
+            picker.color_input.handle_key_event(key);
+            picker.blink_start = picker.animation_phase;
+            if picker.color_input.input != before {
+                picker.original = original;
+                picker.advance_if_complete();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn update_grid_position(model: &mut Model, key_code: KeyCode) {
+        let picker = &mut model.color_picker;
+
+        // The histogram lists the swatches in one column, so every arrow steps by one
+        if picker.histogram_active() {
+            let (_, cols) = picker.grid_dimensions;
+            let (row, col) = picker.grid_index;
+            let index = row * cols + col;
+            let last = picker.colors.len().saturating_sub(1);
+            let index = match key_code {
+                KeyCode::Up | KeyCode::Left => index.saturating_sub(1),
+                KeyCode::Down | KeyCode::Right => (index + 1).min(last),
+                KeyCode::Home | KeyCode::PageUp => 0,
+                KeyCode::End | KeyCode::PageDown => last,
+                _ => index,
+            };
+            picker.grid_index = (index / cols.max(1), index % cols.max(1));
+            return;
+        }
+
+        picker.grid_index = next_index(picker.grid_index, picker.grid_dimensions, key_code);
+    }
+
+    /// Step an index within a horizontal strip of `len` swatches
+    fn strip_position(index: usize, len: usize, key_code: KeyCode) -> usize {
+        let max_index = len.saturating_sub(1);
+
+        match key_code {
+            KeyCode::Left => index.saturating_sub(1),
+            KeyCode::Right => (index + 1).min(max_index),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Keys that jump straight to a grid column, keeping the row: `1`-`9` and `0`
+/// for the first ten hues, then the shifted digits `!`-`^` on a US layout for
+/// the rest of a 16-hue palette
+const HUE_KEYS: &str = "1234567890!@#$%^";
+
+/// Column a hue key jumps to
+fn hue_column(c: char) -> Option<usize> {
+    HUE_KEYS.find(c)
+}
+
+/// Move `(row, col)` within a `(rows, cols)` grid in response to a navigation
+/// key, stopping at the edges. Other keys leave the index unchanged.
+pub fn next_index(
+    (row, col): (usize, usize),
+    (rows, cols): (usize, usize),
+    key_code: KeyCode,
+) -> (usize, usize) {
+    let max_row = rows.saturating_sub(1);
+    let max_col = cols.saturating_sub(1);
+
+    match key_code {
+        KeyCode::Up => (row.saturating_sub(1), col),
+        KeyCode::Down => ((row + 1).min(max_row), col),
+        KeyCode::Left => (row, col.saturating_sub(1)),
+        KeyCode::Right => (row, (col + 1).min(max_col)),
+        KeyCode::Home => (row, 0),
+        KeyCode::End => (row, max_col),
+        KeyCode::PageUp => (0, col),
+        KeyCode::PageDown => (max_row, col),
+        _ => (row, col),
+    }
+}
+
+pub fn update(model: &mut Model, message: Message) -> Result<bool> {
+    match message {
+        Message::KeyPress(key) if key.kind == KeyEventKind::Press => handle_key_press(model, key),
+        Message::Mouse(mouse) => handle_mouse(model, mouse),
+        Message::Paste(text) => {
+            let picker = &mut model.color_picker;
+            if picker.modal_state {
+                let original = picker.original.or_else(|| picker.current_color());
+                if picker.color_input.paste(&text) {
+                    picker.original = original;
+                } else {
+                    picker.show_toast("Invalid hex");
+                }
+            }
+            Ok(true)
+        }
+        Message::UpdateColorFromGrid => {
+            update_color_from_grid(model);
+            Ok(true)
+        }
+        Message::UpdateColorFromRecent => {
+            if let Some(color) = model.color_picker.selected_recent() {
+                model.color_picker.load_color(color);
+            }
+            Ok(true)
+        }
+        Message::UpdateColorFromFavorites => {
+            if let Some(color) = model.color_picker.selected_favorite() {
+                model.color_picker.load_color(color);
+            }
+            Ok(true)
+        }
+        Message::CyclePalette => {
+            model.color_picker.cycle_palette();
+            update_color_from_grid(model);
+            Ok(true)
+        }
+        Message::CycleHarmony => {
+            model.color_picker.cycle_harmony();
+            update_color_from_grid(model);
+            Ok(true)
+        }
+        Message::RandomSwatch => {
+            model.color_picker.select_random();
+            update_color_from_grid(model);
+            Ok(true)
+        }
+        Message::RandomColor => {
+            let color = Color::Rgb(fastrand::u8(..), fastrand::u8(..), fastrand::u8(..));
+            model.color_picker.load_color(color);
+            Ok(true)
+        }
+        Message::ToggleFavorite => {
+            model.color_picker.toggle_favorite();
+            Ok(true)
+        }
+        Message::ToggleSelection => {
+            model.color_picker.toggle_selection();
+            Ok(true)
+        }
+        Message::UpdateColorFromShades => {
+            if let Some(color) = model.color_picker.selected_shade() {
+                model.color_picker.load_color(color);
+            }
+            Ok(true)
+        }
+        Message::UpdateColorFromSliders => {
+            let color = model.color_picker.sliders.color();
+            set_edited_color(model, color);
+            Ok(true)
+        }
+        Message::ToggleSliders => {
+            model.color_picker.toggle_sliders();
+            Ok(true)
+        }
+        Message::UpdateColorFromHsl => {
+            let color = model.color_picker.hsl.color();
+            set_edited_color(model, color);
+            Ok(true)
+        }
+        Message::ToggleHsl => {
+            model.color_picker.toggle_hsl();
+            Ok(true)
+        }
+        Message::ToggleHistogram => {
+            model.color_picker.toggle_histogram();
+            Ok(true)
+        }
+        Message::CycleVisionMode => {
+            let picker = &mut model.color_picker;
+            picker.vision = picker.vision.next();
+            Ok(true)
+        }
+        Message::MoveModal(dx, dy) => {
+            model.color_picker.move_modal(dx, dy);
+            Ok(true)
+        }
+        Message::MarkGradient => {
+            model.color_picker.mark_gradient();
+            Ok(true)
+        }
+        Message::AdjustGradientSteps(delta) => {
+            let picker = &mut model.color_picker;
+            picker.gradient.adjust_steps(delta);
+            picker.shade_index = picker.shade_index.min(picker.gradient.steps - 1);
+            Ok(true)
+        }
+        Message::ToggleGradientBlend => {
+            let gradient = &mut model.color_picker.gradient;
+            gradient.naive = !gradient.naive;
+            Ok(true)
+        }
+        Message::StartSearch => {
+            model.color_picker.start_search();
+            Ok(true)
+        }
+        Message::ClearSearch => {
+            model.color_picker.clear_search();
+            Ok(true)
+        }
+        Message::ToggleEyedropper => {
+            model.color_picker.toggle_eyedropper();
+            Ok(true)
+        }
+        Message::SampleEyedropper => {
+            let picker = &mut model.color_picker;
+            if let Some(color) = picker.eyedropper_sample.get() {
+                picker.load_color(color);
+            }
+            picker.focus = picker.palette_focus();
+            Ok(true)
+        }
+        Message::CycleInputMode => {
+            model.color_picker.color_input.cycle_mode();
+            Ok(true)
+        }
+        Message::CopyColor => {
+            copy_color(model);
+            Ok(true)
+        }
+        Message::CopyEscape => {
+            copy_escape(model);
+            Ok(true)
+        }
+        Message::ToggleEscapeLayer => {
+            let picker = &mut model.color_picker;
+            picker.escape_background = !picker.escape_background;
+            Ok(true)
+        }
+        Message::Reset => {
+            model.color_picker.reset();
+            Ok(true)
+        }
+        Message::CommitInput => {
+            model.color_picker.commit_input();
+            Ok(true)
+        }
+        Message::ApplyColor => {
+            // Enter on a swatch applies that swatch, not whatever was last typed
+            if model.color_picker.focus == Focus::Grid {
+                update_color_from_grid(model);
+            }
+            apply_color(model);
+            if model.color_picker.locked {
+                return Ok(true);
+            }
+
+            model.outcome = model
+                .applied_color
+                .map_or(Outcome::Cancelled, Outcome::Applied);
+            Ok(false)
+        }
+        Message::ToggleLock => {
+            let picker = &mut model.color_picker;
+            picker.locked = !picker.locked;
+            Ok(true)
+        }
+        Message::ToggleLabels => {
+            let picker = &mut model.color_picker;
+            picker.show_labels = !picker.show_labels;
+            Ok(true)
+        }
+        Message::ToggleGaps => {
+            let picker = &mut model.color_picker;
+            picker.grid_gaps = !picker.grid_gaps;
+            Ok(true)
+        }
+        Message::Nudge(delta) => {
+            model.color_picker.nudge(delta);
+            Ok(true)
+        }
+        Message::Invert => {
+            model.color_picker.invert();
+            Ok(true)
+        }
+        Message::Complement => {
+            model.color_picker.complement();
+            Ok(true)
+        }
+        Message::CycleNudgeChannel(delta) => {
+            model.color_picker.cycle_nudge_channel(delta);
+            Ok(true)
+        }
+        Message::AdjustLightness(delta) => {
+            model.color_picker.adjust_lightness(delta);
+            if model.color_picker.focus == Focus::Grid {
+                update_color_from_grid(model);
+            }
+            Ok(true)
+        }
+        Message::CancelColorSelection => {
+            let picker = &mut model.color_picker;
+            picker.confirm_discard = picker.has_unsaved_input();
+            if !picker.confirm_discard {
+                model.outcome = Outcome::Cancelled;
+            }
+            Ok(picker.confirm_discard)
+        }
+        Message::ConfirmDiscard(discard) => {
+            model.color_picker.confirm_discard = false;
+            if discard {
+                model.outcome = Outcome::Cancelled;
+            }
+            Ok(!discard)
+        }
+        Message::ToggleModal => {
+            toggle_modal(model);
+            Ok(true)
+        }
+        Message::FocusNext => {
+            model.color_picker.focus_next();
+            Ok(true)
+        }
+        Message::FocusPrev => {
+            model.color_picker.focus_prev();
+            Ok(true)
+        }
+        Message::Quit => {
+            model.outcome = Outcome::Quit;
+            Ok(false)
+        }
+        Message::Tick => {
+            let picker = &mut model.color_picker;
+            picker.expire_toast();
+            picker.animation_phase = picker.animation_phase.wrapping_add(1);
+            Ok(true)
+        }
+        Message::Ignore => Ok(true),
+        _ => Ok(true),
+    }
+}
+
+fn handle_key_press(model: &mut Model, key: KeyEvent) -> Result<bool> {
+    if model.color_picker.confirm_discard {
+        return update(model, KeyHandler::handle_confirm_keys(key));
+    }
+
+    if model.color_picker.modal_state
+        && model.color_picker.focus == Focus::Search
+        && let Some(message) = KeyHandler::handle_search_keys(model, key)
+    {
+        return update(model, message);
+    }
+
+    if let Some(message) = KeyHandler::handle_global_keys(model, key) {
+        return update(model, message);
+    }
+
+    if model.color_picker.modal_state {
+        if let Some(message) = KeyHandler::handle_nudge_keys(model, key) {
+            return update(model, message);
+        }
+
+        if let Some(message) = KeyHandler::handle_modal_navigation(model, key) {
+            return update(model, message);
+        }
+
+        if let Some(message) = KeyHandler::handle_modal_actions(model, key) {
+            return update(model, message);
+        }
+
+        if KeyHandler::handle_input_keys(model, key) {
+            return Ok(true);
+        }
+    }
+
+    Ok(true)
+}
+
+fn handle_mouse(model: &mut Model, mouse: MouseEvent) -> Result<bool> {
+    let picker = &mut model.color_picker;
+    if !picker.modal_state {
+        return Ok(true);
+    }
+
+    let position = Position::new(mouse.column, mouse.row);
+
+    // The wheel moves the selection a row at a time, scrolling the grid with it
+    let over_grid = !picker.show_sliders && picker.grid_area.get().contains(position);
+    let wheel = match mouse.kind {
+        MouseEventKind::ScrollUp => Some(KeyCode::Up),
+        MouseEventKind::ScrollDown => Some(KeyCode::Down),
+        _ => None,
+    };
+    if let Some(code) = wheel.filter(|_| over_grid) {
+        picker.grid_index = next_index(picker.grid_index, picker.grid_dimensions, code);
+        picker.focus = Focus::Grid;
+        return update(model, Message::UpdateColorFromGrid);
+    }
+
+    if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+        return Ok(true);
+    }
+
+    if let Some(grid_index) = model.color_picker.grid_position_at(position) {
+        model.color_picker.grid_index = grid_index;
+        model.color_picker.focus = Focus::Grid;
+        return update(model, Message::UpdateColorFromGrid);
+    }
+
+    Ok(true)
+}
+
+fn update_color_from_grid(model: &mut Model) {
+    if let Some(color) = model.color_picker.selected_color() {
+        model.color_picker.load_color(color);
+    }
+}
+
+/// Write a color tuned in the sliders or HSL gauges to the input. Picking
+/// another channel sends it too without changing the color, which doesn't
+/// count as an edit.
+fn set_edited_color(model: &mut Model, color: Color) {
+    let picker = &mut model.color_picker;
+    if picker.current_color() != Some(color) {
+        picker.begin_edit();
+    }
+    picker.color_input.set_color(color);
+}
+
+fn apply_color(model: &mut Model) {
+    let picker = &mut model.color_picker;
+
+    // The input can't hold indexed colors, so take swatches straight from the grid
+    let selected = picker.selected_color();
+    let (color, alpha) = if picker.focus == Focus::Grid {
+        (selected, None)
+    } else {
+        let input = &picker.color_input;
+        let alpha = input.alpha();
+        let color = match input.color() {
+            // Still the swatch's RGB approximation, so keep the indexed color itself
+            Some(typed)
+                if alpha.is_none()
+                    && approximate_rgb(typed) == selected.and_then(approximate_rgb) =>
+            {
+                selected
+            }
+            typed => typed.or(selected),
+        };
+        (color, alpha)
+    };
+    let Some(color) = color else {
+        return;
+    };
+
+    picker.push_recent(color);
+    picker.alpha = alpha;
+    model.applied_color = Some(color);
+    let css_name = model.css_name.as_deref();
+    model.selected_output = model
+        .output_format
+        .format_named(color, picker.alpha, css_name);
+
+    // A selection or a complete gradient is written out in full
+    if !picker.selection.is_empty() {
+        model.selected_output = Some(model.output_format.format_list(&picker.selection, css_name));
+    } else if picker.gradient.is_complete() {
+        let stops = picker.gradient.stops();
+        model.selected_output = Some(model.output_format.format_list(&stops, css_name));
+    }
+
+    if let Some(sender) = &model.apply_sender {
+        // The receiver going away shouldn't stop the picker
+        let _ = sender.send(color);
+    }
+    if picker.locked {
+        picker.show_toast("Applied!");
+    }
+}
+
+fn copy_color(model: &mut Model) {
+    let input = &model.color_picker.color_input;
+    let Some(hex) = input
+        .color()
+        .and_then(|color| OutputFormat::Hex.format(color, input.alpha()))
+    else {
+        model.color_picker.show_toast("Invalid color");
+        return;
+    };
+
+    let message = match clipboard::set_text(hex) {
+        Ok(()) => "Copied!",
+        Err(_) => "Couldn't copy",
+    };
+    model.color_picker.show_toast(message);
+}
+
+fn copy_escape(model: &mut Model) {
+    let picker = &mut model.color_picker;
+    let Some(sequence) = picker.escape_sequence() else {
+        picker.show_toast("Invalid color");
+        return;
+    };
+
+    let message = match clipboard::set_text(sequence) {
+        Ok(()) => "Copied escape!",
+        Err(_) => "Couldn't copy",
+    };
+    picker.show_toast(message);
+}
+
+fn toggle_modal(model: &mut Model) {
+    model.color_picker.modal_state = !model.color_picker.modal_state;
+
+    if model.color_picker.modal_state {
+        update_color_from_grid(model);
+    }
+
+    let picker = &mut model.color_picker;
+    if picker.modal_state && picker.start_on_input {
+        picker.focus = Focus::Input;
+    }
+}
+
+pub fn handle_event() -> Result<Message> {
+    Ok(event_message(event::read()?))
+}
+
+/// The message for a terminal event
+pub fn event_message(event: event::Event) -> Message {
+    match event {
+        event::Event::Key(key) => Message::KeyPress(key),
+        event::Event::Mouse(mouse) => Message::Mouse(mouse),
+        event::Event::Paste(text) => Message::Paste(text),
+        // Resizes and focus changes just trigger a redraw
+        _ => Message::Ignore,
+    }
+}
+
+/// Like [`handle_event`], but gives up after `timeout` with [`Message::Tick`]
+pub fn poll_event(timeout: Duration) -> Result<Message> {
+    if event::poll(timeout)? {
+        handle_event()
+    } else {
+        Ok(Message::Tick)
+    }
+}
+
+pub fn view<B: Backend>(model: &Model, terminal: &mut Terminal<B>) {
+    terminal
+        .draw(|frame| {
+            frame.render_widget(&model.color_picker, frame.area());
+        })
+        .expect("Couldn't draw the UI");
+}
+
+/// Run the color picker until the user applies or cancels.
+/// Returns the applied color, or `None` if the picker was dismissed.
+pub fn pick_color() -> Result<Option<Color>> {
+    Ok(run_picker(Model::default())?.applied_color())
+}
+
+/// Run the color picker starting from `model` and return the final model for inspection
+pub fn run_picker(model: Model) -> Result<Model> {
+    let (mut terminal, guard) = init_terminal(Viewport::Fullscreen)?;
+    let result = run(&mut terminal, model);
+    drop(guard);
+    result
+}
+
+/// Rows reserved below the cursor when running inline
+const INLINE_HEIGHT: u16 = 24;
+
+/// Like [`run_picker`], but draws inline below the cursor instead of switching to the
+/// alternate screen, so the picker stays in the scrollback after exit
+pub fn run_picker_inline(mut model: Model) -> Result<Model> {
+    // Inline fills the viewport, so keep the preferred layout for the next run
+    let picker = &model.color_picker;
+    let layout = (picker.modal_size, picker.modal_offset.get());
+    model.color_picker.modal_size = (100, 100);
+
+    let (mut terminal, guard) = init_terminal(inline_viewport()?)?;
+    let result = run(&mut terminal, model);
+
+    // No alternate screen to leave: just park the cursor below the picker
+    let bottom = terminal.get_frame().area().bottom();
+    terminal.set_cursor_position(Position::new(0, bottom.saturating_sub(1)))?;
+    drop(guard);
+    eprintln!();
+
+    result.map(|mut model| {
+        let (size, offset) = layout;
+        model.color_picker.modal_size = size;
+        model.color_picker.modal_offset.set(offset);
+        model
+    })
+}
+
+/// Below the cursor when stdout is the terminal. crossterm asks the terminal
+/// where the cursor is through stdout, so when that's redirected the picker
+/// instead takes the bottom rows, scrolling blank lines up to make room.
+fn inline_viewport() -> Result<Viewport> {
+    if io::stdout().is_terminal() {
+        return Ok(Viewport::Inline(INLINE_HEIGHT));
+    }
+
+    let (width, rows) = terminal::size()?;
+    let height = INLINE_HEIGHT.min(rows);
+    eprint!("{}", "\n".repeat(usize::from(height)));
+    Ok(Viewport::Fixed(Rect::new(0, rows - height, width, height)))
+}
+
+/// The picker draws on stderr, leaving stdout to the applied color alone so
+/// `$(color-picker)` captures just that
+pub type PickerBackend = CrosstermBackend<BufWriter<Stderr>>;
+
+/// Put the terminal into raw mode with mouse and paste capture, on the
+/// alternate screen unless the viewport is inline. The terminal is restored
+/// when the returned guard drops, or on a panic, whatever fails part way.
+fn init_terminal(viewport: Viewport) -> Result<(Terminal<PickerBackend>, TerminalGuard)> {
+    let alternate = viewport == Viewport::Fullscreen;
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal(alternate);
+        hook(info);
+    }));
+
+    enable_raw_mode()?;
+    let guard = TerminalGuard { alternate };
+    if alternate {
+        execute!(stderr(), EnterAlternateScreen)?;
+    }
+    execute!(stderr(), EnableMouseCapture, EnableBracketedPaste)?;
+
+    let backend = CrosstermBackend::new(BufWriter::new(stderr()));
+    let terminal = Terminal::with_options(backend, TerminalOptions { viewport })?;
+    Ok((terminal, guard))
+}
+
+/// Restores the terminal when dropped, see [`init_terminal`]
+struct TerminalGuard {
+    alternate: bool,
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(self.alternate);
+    }
+}
+
+/// Undo [`init_terminal`]. Every step is tried even if an earlier one fails,
+/// since there's no better way left to report it.
+fn restore_terminal(alternate: bool) {
+    let mut stderr = stderr();
+    let _ = execute!(stderr, DisableMouseCapture, DisableBracketedPaste);
+    if alternate {
+        let _ = execute!(stderr, LeaveAlternateScreen);
+    }
+    let _ = execute!(stderr, Show);
+    let _ = disable_raw_mode();
+    let _ = stderr.flush();
+}
+
+fn run(terminal: &mut Terminal<PickerBackend>, mut model: Model) -> Result<Model> {
+    update(&mut model, Message::ToggleModal)?;
+
+    let mut running = true;
+    while running {
+        view(&model, terminal);
+
+        let message = poll_event(TICK_RATE)?;
+        running = update(&mut model, message)?;
+    }
+
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::Event;
+
+    use super::*;
+
+    /// A model with the modal open, as `run` starts it
+    fn open_model() -> Model {
+        let mut model = Model::default();
+        update(&mut model, Message::ToggleModal).unwrap();
+        model
+    }
+
+    #[test]
+    fn focus_and_resize_events_keep_running() {
+        for event in [Event::FocusGained, Event::FocusLost, Event::Resize(80, 24)] {
+            let mut model = open_model();
+            let message = event_message(event.clone());
+
+            assert!(
+                matches!(message, Message::Ignore),
+                "{event:?} gave {message:?}"
+            );
+            assert!(
+                update(&mut model, message).unwrap(),
+                "{event:?} quit the picker"
+            );
+            assert!(model.color_picker.modal_state);
+        }
+    }
+
+    #[test]
+    fn next_index_clamps_at_the_edges() {
+        use KeyCode::*;
+
+        // (index, dimensions, key, expected)
+        let cases = [
+            // Inside a 3x4 grid every arrow moves one step
+            ((1, 1), (3, 4), Up, (0, 1)),
+            ((1, 1), (3, 4), Down, (2, 1)),
+            ((1, 1), (3, 4), Left, (1, 0)),
+            ((1, 1), (3, 4), Right, (1, 2)),
+            // First and last row and column clamp instead of wrapping
+            ((0, 2), (3, 4), Up, (0, 2)),
+            ((2, 2), (3, 4), Down, (2, 2)),
+            ((1, 0), (3, 4), Left, (1, 0)),
+            ((1, 3), (3, 4), Right, (1, 3)),
+            ((0, 0), (3, 4), Up, (0, 0)),
+            ((2, 3), (3, 4), Right, (2, 3)),
+            // Jumps to the ends of the row and column
+            ((1, 1), (3, 4), Home, (1, 0)),
+            ((1, 1), (3, 4), End, (1, 3)),
+            ((1, 1), (3, 4), PageUp, (0, 1)),
+            ((1, 1), (3, 4), PageDown, (2, 1)),
+            // A single row only moves sideways
+            ((0, 2), (1, 5), Up, (0, 2)),
+            ((0, 2), (1, 5), Down, (0, 2)),
+            ((0, 2), (1, 5), Right, (0, 3)),
+            ((0, 2), (1, 5), PageDown, (0, 2)),
+            // A single column only moves up and down
+            ((2, 0), (5, 1), Left, (2, 0)),
+            ((2, 0), (5, 1), Right, (2, 0)),
+            ((2, 0), (5, 1), Down, (3, 0)),
+            ((2, 0), (5, 1), End, (2, 0)),
+            // An empty grid pins everything to the origin
+            ((0, 0), (0, 0), Down, (0, 0)),
+            ((0, 0), (0, 0), Right, (0, 0)),
+            ((0, 0), (0, 0), End, (0, 0)),
+            ((0, 0), (0, 0), PageDown, (0, 0)),
+            // Other keys leave the index alone
+            ((1, 1), (3, 4), Enter, (1, 1)),
+            ((1, 1), (3, 4), Char('x'), (1, 1)),
+        ];
+
+        for (index, dimensions, key, expected) in cases {
+            assert_eq!(
+                next_index(index, dimensions, key),
+                expected,
+                "{key:?} from {index:?} in {dimensions:?}"
+            );
+        }
+    }
+
+    fn press(model: &mut Model, code: KeyCode) {
+        assert!(update(model, Message::KeyPress(KeyEvent::from(code))).unwrap());
+    }
+
+    #[test]
+    fn home_end_and_page_keys_jump_across_the_grid() {
+        let (rows, cols) = open_model().color_picker.grid_dimensions;
+        let middle = (rows / 2, cols / 2);
+        let cases = [
+            (KeyCode::Home, (middle.0, 0)),
+            (KeyCode::End, (middle.0, cols - 1)),
+            (KeyCode::PageUp, (0, middle.1)),
+            (KeyCode::PageDown, (rows - 1, middle.1)),
+        ];
+
+        for (key, expected) in cases {
+            let mut model = open_model();
+            model.color_picker.grid_index = middle;
+            press(&mut model, key);
+
+            assert_eq!(model.color_picker.grid_index, expected, "{key:?}");
+        }
+    }
+
+    #[test]
+    fn home_end_and_page_keys_reach_the_histogram_ends() {
+        let mut model = open_model();
+        let picker = &mut model.color_picker;
+        let (rows, cols) = picker.grid_dimensions;
+        picker.weights = Some(vec![1.0; picker.colors.len()]);
+        picker.show_histogram = true;
+        let middle = (rows / 2, cols / 2);
+        let last = picker.colors.len() - 1;
+
+        for (key, expected) in [
+            (KeyCode::Home, (0, 0)),
+            (KeyCode::End, (last / cols, last % cols)),
+            (KeyCode::PageUp, (0, 0)),
+            (KeyCode::PageDown, (last / cols, last % cols)),
+        ] {
+            model.color_picker.grid_index = middle;
+            press(&mut model, key);
+
+            assert_eq!(model.color_picker.grid_index, expected, "{key:?}");
+        }
+    }
+
+    /// A model focused on an emptied input with `text` typed into it
+    fn typed_model(text: &str) -> Model {
+        let mut model = open_model();
+        model.color_picker.focus = Focus::Input;
+        let input = &mut model.color_picker.color_input;
+        input.input.clear();
+        input.cursor_pos = 0;
+        for c in text.chars() {
+            press(&mut model, KeyCode::Char(c));
+        }
+        assert_eq!(
+            model.color_picker.color_input.input.to_lowercase(),
+            text.to_lowercase()
+        );
+        model
+    }
+
+    #[test]
+    fn invert_and_complement_work_while_typing() {
+        let mut model = typed_model("ff8800");
+        press(&mut model, KeyCode::Char('~'));
+        assert_eq!(model.color_picker.color_input.input, "0077FF");
+
+        press(&mut model, KeyCode::Char('k'));
+        assert_eq!(model.color_picker.color_input.input, "FF8800");
+
+        // Still typed when it spells a name
+        let mut model = typed_model("blac");
+        press(&mut model, KeyCode::Char('k'));
+        assert_eq!(model.color_picker.color_input.input, "black");
+    }
+
+    #[test]
+    fn indexed_gradient_applies_its_stops() {
+        let mut model = open_model();
+        model.color_picker.set_builtin_palette(Palette::Ansi16);
+        model.color_picker.focus = Focus::Grid;
+        model.color_picker.gradient.mark(Color::Indexed(0));
+        model.color_picker.gradient.mark(Color::Indexed(15));
+        apply_color(&mut model);
+
+        let output = model.selected_output.unwrap();
+        assert_eq!(output.lines().count(), model.color_picker.gradient.steps);
+        assert!(output.starts_with("#000000"), "{output:?}");
+    }
+
+    #[test]
+    fn enter_on_a_valid_input_moves_to_apply() {
+        for text in ["ff8800", "#abc", "teal", "rgb(1,2,3)"] {
+            let mut model = typed_model(text);
+            press(&mut model, KeyCode::Enter);
+
+            let picker = &model.color_picker;
+            assert_eq!(picker.focus, Focus::Apply, "{text:?}");
+            assert_eq!(picker.rejected_at, None, "{text:?}");
+            assert!(picker.modal_state);
+        }
+    }
+
+    #[test]
+    fn enter_on_an_invalid_input_flashes_and_stays() {
+        for text in ["", "ff88", "zz", "rgb(300,0,0)"] {
+            let mut model = typed_model(text);
+            press(&mut model, KeyCode::Enter);
+
+            let picker = &model.color_picker;
+            assert_eq!(picker.focus, Focus::Input, "{text:?}");
+            assert!(picker.rejected_at.is_some(), "{text:?}");
+            assert!(picker.modal_state);
+            assert_eq!(model.applied_color, None);
+        }
+    }
+
+    /// A model on the ANSI 16 palette's red swatch, with the input focused
+    fn ansi_model() -> Model {
+        let mut model = open_model();
+        model.color_picker.set_builtin_palette(Palette::Ansi16);
+        model.color_picker.grid_index = (0, 1);
+        update(&mut model, Message::UpdateColorFromGrid).unwrap();
+        model.color_picker.focus = Focus::Input;
+
+        assert_eq!(model.color_picker.selected_color(), Some(Color::Indexed(1)));
+        assert_eq!(model.color_picker.color_input.input, "CD0000");
+        model
+    }
+
+    #[test]
+    fn indexed_swatch_in_the_input_isnt_unsaved() {
+        let mut model = ansi_model();
+
+        assert!(!update(&mut model, Message::KeyPress(KeyEvent::from(KeyCode::Esc))).unwrap());
+        assert!(!model.color_picker.confirm_discard);
+        assert!(matches!(model.outcome, Outcome::Cancelled));
+    }
+
+    #[test]
+    fn applying_an_untouched_indexed_swatch_keeps_the_index() {
+        let mut model = ansi_model();
+        apply_color(&mut model);
+        assert_eq!(model.applied_color, Some(Color::Indexed(1)));
+
+        // Typing a different color applies what was typed
+        let mut model = ansi_model();
+        model.color_picker.color_input.paste("#CD0001");
+        assert!(model.color_picker.has_unsaved_input());
+        apply_color(&mut model);
+        assert_eq!(model.applied_color, Some(Color::Rgb(0xCD, 0x00, 0x01)));
+    }
+}
@@ -1,202 +1,149 @@
-use color_eyre::Result;
-use crossterm::event;
-use ratatui::{
-    Terminal,
-    crossterm::event::{KeyCode, KeyEvent, KeyEventKind},
-    prelude::CrosstermBackend,
+use std::{
+    fs,
+    io::{self, Write},
 };
-use std::io::Stdout;
 
-use crate::modal::{ColorPickerWidget, Focus};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+use color_picker_ratatui::{
+    Model, Outcome,
+    config::Config,
+    output::Gamut,
+    palette::{Palette, load_image_palette, load_palette},
+    run_picker, run_picker_inline,
+    util::{
+        clipboard,
+        color_support::{ColorSupport, approximate_rgb},
+        convert::rgb_to_hex,
+        parse::parse_color,
+    },
+};
 
-mod button;
-mod color_input;
-mod modal;
-mod util {
-    pub mod styles;
-}
+use crate::cli::Args;
 
-#[derive(Debug, Default)]
-pub struct Model {
-    color_picker: ColorPickerWidget,
-}
+mod cli;
 
-#[derive(Debug)]
-pub enum Message {
-    KeyPress(KeyEvent),
-    ToggleModal,
-    ApplyColor,
-    UpdateColorFromGrid,
-    CancelColorSelection,
-    FocusNext,
-    FocusPrev,
-    Quit,
-    Ignore,
-}
+/// Number of dominant colors taken from an `--image`
+const IMAGE_COLORS: usize = 16;
 
-// Centralized key mapping
-struct KeyHandler;
+fn main() -> Result<()> {
+    color_eyre::install()?;
 
-impl KeyHandler {
-    fn handle_global_keys(key: KeyEvent) -> Option<Message> {
-        match key.code {
-            KeyCode::Esc | KeyCode::Char('q' | 'Q') => Some(Message::Quit),
-            KeyCode::Char('p' | 'P') => Some(Message::ToggleModal),
-            _ => None,
-        }
+    // A background copy of the picker keeping the applied color on the clipboard
+    let mut raw = std::env::args().skip(1);
+    if raw.next().as_deref() == Some(clipboard::HOLD_FLAG) {
+        let _ = clipboard::hold(raw.next().unwrap_or_default());
+        return Ok(());
     }
 
-    fn handle_modal_navigation(model: &mut Model, key: KeyEvent) -> Option<Message> {
-        if model.color_picker.focus != Focus::Grid {
-            return None;
-        }
+    let args = Args::parse()?;
 
-        match key.code {
-            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
-                Self::update_grid_position(model, key.code);
-                Some(Message::UpdateColorFromGrid)
-            }
-            _ => None,
-        }
+    if args.gamut == Gamut::P3 {
+        eprintln!("Colors are sRGB; they aren't converted to Display P3");
     }
 
-    fn handle_modal_actions(model: &Model, key: KeyEvent) -> Option<Message> {
-        match key.code {
-            KeyCode::Tab => Some(Message::FocusNext),
-            KeyCode::BackTab => Some(Message::FocusPrev),
-            KeyCode::Enter => match model.color_picker.focus {
-                Focus::Apply => Some(Message::ApplyColor),
-                Focus::Cancel => Some(Message::CancelColorSelection),
-                _ => None,
-            },
-            KeyCode::Esc => Some(Message::CancelColorSelection),
-            _ => None,
+    // A config that doesn't parse is left alone rather than saved over with
+    // the defaults, so fixing it by hand loses nothing
+    let (mut config, save_config) = match Config::load() {
+        Ok(config) => (config, true),
+        Err(err) => {
+            eprintln!("{err:#}; using the defaults");
+            (Config::default(), false)
         }
-    }
-
-    fn handle_input_keys(model: &mut Model, key: KeyEvent) -> bool {
-        if model.color_picker.focus == Focus::Input {
-            model.color_picker.color_input.handle_key_event(key);
-            true
+    };
+    let mut model = Model::from_config(&config)
+        .output_format(args.format)
+        .show_labels(args.labels)
+        .animations(!args.no_animations)
+        .blink(!args.no_blink)
+        .compact(args.compact)
+        .auto_advance(args.auto_advance)
+        .theme(args.theme)
+        .color_support(if args.force_truecolor {
+            ColorSupport::TrueColor
         } else {
-            false
+            ColorSupport::detect()
+        });
+
+    if let Some(path) = &args.palette {
+        let colors = load_palette(path)
+            .wrap_err_with(|| format!("Couldn't load the palette `{}`", path.display()))?;
+
+        if colors.is_empty() {
+            eprintln!(
+                "No valid colors in `{}`, using the default palette",
+                path.display()
+            );
+        } else {
+            model = model.palette(colors);
         }
     }
-
-    fn update_grid_position(model: &mut Model, key_code: KeyCode) {
-        let (mut row, mut col) = model.color_picker.grid_index;
-        let (rows, cols) = model.color_picker.grid_dimensions;
-        let max_row = rows.saturating_sub(1);
-        let max_col = cols.saturating_sub(1);
-
-        match key_code {
-            KeyCode::Up => row = row.saturating_sub(1),
-            KeyCode::Down => row = (row + 1).min(max_row),
-            KeyCode::Left => col = col.saturating_sub(1),
-            KeyCode::Right => col = (col + 1).min(max_col),
-            _ => unreachable!(),
-        }
-
-        model.color_picker.grid_index = (row, col);
+    if let Some(path) = &args.image {
+        let colors = load_image_palette(path, IMAGE_COLORS)
+            .wrap_err_with(|| format!("Couldn't read colors from `{}`", path.display()))?;
+        model = model.weighted_palette(colors);
     }
-}
-
-pub fn update(model: &mut Model, message: Message) -> Result<bool> {
-    match message {
-        Message::KeyPress(key) if key.kind == KeyEventKind::Press => handle_key_press(model, key),
-        Message::UpdateColorFromGrid => {
-            update_color_from_grid(model);
-            Ok(true)
-        }
-        Message::ApplyColor => Ok(false),
-        Message::CancelColorSelection => Ok(false),
-        Message::ToggleModal => {
-            toggle_modal(model);
-            Ok(true)
-        }
-        Message::FocusNext => {
-            model.color_picker.focus_next();
-            Ok(true)
-        }
-        Message::FocusPrev => {
-            model.color_picker.focus_prev();
-            Ok(true)
-        }
-        Message::Quit => Ok(false),
-        Message::Ignore => Ok(true),
-        _ => Ok(true),
+    if let Some(name) = &args.css_name {
+        model = model.css_name(name);
     }
-}
-
-fn handle_key_press(model: &mut Model, key: KeyEvent) -> Result<bool> {
-    if let Some(message) = KeyHandler::handle_global_keys(key) {
-        return update(model, message);
+    if let Some(width) = args.swatch_width {
+        model = model.swatch_width(width);
     }
 
-    if model.color_picker.modal_state {
-        if let Some(message) = KeyHandler::handle_modal_navigation(model, key) {
-            return update(model, message);
-        }
-
-        if let Some(message) = KeyHandler::handle_modal_actions(model, key) {
-            return update(model, message);
-        }
-
-        if KeyHandler::handle_input_keys(model, key) {
-            return Ok(true);
-        }
+    if args.focus_input {
+        model = model.start_on_input(true);
     }
 
-    Ok(true)
-}
-
-fn update_color_from_grid(model: &mut Model) {
-    if let Some(color) = model.color_picker.selected_color()
-        && let Some(hex) = ColorPickerWidget::color_to_hex(color)
-    {
-        model.color_picker.color_input.input = hex.clone();
-        model.color_picker.color_input.cursor_pos = hex.len();
+    if args.ansi_256 {
+        model = model.builtin_palette(Palette::Ansi256);
     }
-}
-
-fn toggle_modal(model: &mut Model) {
-    model.color_picker.modal_state = !model.color_picker.modal_state;
 
-    if model.color_picker.modal_state {
-        update_color_from_grid(model);
+    if args.dump_palette {
+        // A reader that stops early, like `head`, isn't an error
+        return match writeln!(io::stdout(), "{}", model.palette_output()) {
+            Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err.into()),
+            _ => Ok(()),
+        };
     }
-}
 
-pub fn handle_event() -> Result<Message> {
-    match event::read()? {
-        event::Event::Key(key) => Ok(Message::KeyPress(key)),
-        event::Event::Resize(..) => Ok(Message::Ignore),
-        _ => Ok(Message::Quit),
+    // After the palette is chosen, so the right swatches are searched
+    if let Some(text) = &args.color {
+        let Some((color, _)) = parse_color(text) else {
+            bail!("`{text}` isn't a color; try a hex code like `#FF8800` or a name like `orange`");
+        };
+        model = model.initial_color(color);
     }
-}
-
-pub fn view(model: &Model, terminal: &mut Terminal<CrosstermBackend<Stdout>>) {
-    terminal
-        .draw(|frame| {
-            frame.render_widget(&model.color_picker, frame.area());
-        })
-        .expect("Couldn't draw the UI");
-}
 
-pub fn main() -> Result<()> {
-    color_eyre::install()?;
-    let mut terminal = ratatui::init();
+    let model = if args.no_alt_screen {
+        run_picker_inline(model)?
+    } else {
+        run_picker(model)?
+    };
 
-    let mut model = Model::default();
+    model.update_config(&mut config);
+    if save_config && let Err(err) = config.save() {
+        eprintln!("Couldn't save the config: {err}");
+    }
 
-    let mut running = true;
-    while running {
-        view(&model, &mut terminal);
+    // Scripts can tell an applied color (0) from a cancel or quit (1)
+    let (Outcome::Applied(_), Some(output)) = (model.outcome(), model.output()) else {
+        std::process::exit(1);
+    };
 
-        let message = handle_event()?;
-        running = update(&mut model, message)?;
+    // The clipboard always gets plain `#RRGGBB`, whatever the output format
+    let hex = model.applied_color().and_then(approximate_rgb);
+    if let Some((r, g, b)) = hex
+        && let Err(err) = clipboard::set_text_after_exit(rgb_to_hex(r, g, b))
+    {
+        eprintln!("Couldn't copy the color to the clipboard: {err}");
     }
 
-    ratatui::restore();
+    match &args.output {
+        Some(path) => fs::write(path, format!("{output}\n"))
+            .wrap_err_with(|| format!("Couldn't write the color to `{}`", path.display()))?,
+        None => println!("{output}"),
+    }
     Ok(())
 }
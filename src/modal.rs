@@ -1,14 +1,39 @@
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
 use ratatui::{
     buffer::Buffer,
+    crossterm::event::KeyCode,
     layout::{Constraint, Flex, Layout, Position, Rect},
-    style::{Color, Style, palette::material},
-    widgets::{Block, BorderType, Borders, Clear, Widget},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, BorderType, Borders, Clear, Scrollbar, ScrollbarOrientation, ScrollbarState,
+        StatefulWidget, Widget,
+    },
 };
 
 use crate::{
     button::{Button, State},
     color_input::ColorInput,
-    util::styles::Styles,
+    gradient::Gradient,
+    keymap::{Key, KeyMap},
+    output::ansi_escape,
+    palette::{self, Harmony, Palette, PaletteOrder},
+    sliders::{HslGaugesWidget, HslState, HsvSliders, HsvSlidersWidget},
+    util::{
+        color_support::{ColorSupport, approximate_rgb},
+        contrast::{AA_RATIO, AAA_RATIO, contrast_ratio, contrasting_color, relative_luminance},
+        convert::{
+            delta_e, hsl_to_rgb, mix_rgb, rgb_components, rgb_distance, rgb_to_cmyk, rgb_to_hex,
+            rgb_to_hex_bare, rgb_to_hsl,
+        },
+        named_colors::nearest_named,
+        styles::Theme,
+        vision::VisionMode,
+    },
 };
 
 #[derive(Debug)]
@@ -17,117 +42,880 @@ pub struct ColorPickerWidget {
     pub grid_index: (usize, usize),
     pub color_input: ColorInput,
     pub focus: Focus,
+    /// Open on the input instead of the grid, for typing a color straight away
+    pub start_on_input: bool,
+    /// Move focus to Apply as soon as a full six-digit hex is typed
+    pub auto_advance: bool,
     pub colors: Vec<Color>,
     pub grid_dimensions: (usize, usize),
+    /// Built-in palette shown in the grid, or `None` for a custom one
+    pub palette: Option<Palette>,
+    /// Layout of the built-in hue palettes
+    pub palette_order: PaletteOrder,
+    /// Share of the palette's source each color stands for, e.g. of an image's
+    /// pixels, in palette order. `None` for palettes without weights.
+    pub weights: Option<Vec<f64>>,
+    /// Draw the weights as bars in place of the grid
+    pub show_histogram: bool,
+    /// Scheme shown in the grid and the RGB base it was built from, while the
+    /// grid holds a harmony rather than a palette
+    pub harmony: Option<(Harmony, (u8, u8, u8))>,
+    pub recent: Vec<Color>,
+    pub recent_index: usize,
+    /// Colors pinned explicitly; unlike recents they only change on request
+    pub favorites: Vec<Color>,
+    pub favorite_index: usize,
+    /// Colors collected for a palette; when there are any, Apply outputs them all
+    pub selection: Vec<Color>,
+    pub shade_index: usize,
+    /// Alpha of the applied color; `Color::Rgb` has no alpha channel
+    pub alpha: Option<u8>,
+    /// Short notice like `Copied!`, drawn at the bottom of the modal until it expires
+    pub toast: Option<Toast>,
+    /// The color as it was before the first edit since it was picked, compared
+    /// with the edited color below the inputs. `None` until something is edited.
+    pub original: Option<Color>,
+    /// When Enter last found the input unusable, which flashes its border red
+    pub rejected_at: Option<Instant>,
+    /// Modal width and height as percentages of the terminal area
+    pub modal_size: (u16, u16),
+    pub sliders: HsvSliders,
+    /// Show the HSV sliders in place of the palette grid
+    pub show_sliders: bool,
+    pub hsl: HslState,
+    /// Show the HSL gauges below the palette for fine-tuning the current color
+    pub show_hsl: bool,
+    /// Fixed swatch width in columns; `None` stretches swatches to fill the grid
+    pub swatch_width: Option<u16>,
+    /// Draw each grid swatch's hex code on it, where it fits
+    pub show_labels: bool,
+    /// Leave a one-cell gap between grid swatches so similar shades don't blend
+    pub grid_gaps: bool,
+    /// RGB channel the nudge keys change: 0 red, 1 green, 2 blue
+    pub nudge_channel: usize,
+    /// Always show the palette as a single row, as on short terminals
+    pub compact: bool,
+    /// Whether the last render used the compact palette, used for mouse hit-testing
+    pub compact_active: Cell<bool>,
+    /// Pulse the focused button; off for anyone who'd rather avoid motion
+    pub animations: bool,
+    /// Ticks since the picker opened, driving the button pulse and cursor blink
+    pub animation_phase: usize,
+    /// Blink the input cursor, as long as animations are on too
+    pub blink: bool,
+    /// Tick the input cursor last moved on, so it stays solid while typing
+    pub blink_start: usize,
+    /// Colors of the modal chrome
+    pub theme: Theme,
+    /// Colors the terminal can show; swatches are quantized to fit when rendering
+    pub color_support: ColorSupport,
+    /// Percentage points added to the lightness of every palette swatch. The
+    /// palette itself is untouched; what you see is what gets applied.
+    pub lightness: i8,
+    /// Simulated color vision applied to swatches; output stays the true color
+    pub vision: VisionMode,
+    /// Show and copy the background escape sequence instead of the foreground one
+    pub escape_background: bool,
+    /// Keep the modal open after Apply
+    pub locked: bool,
+    /// Asking whether to discard edited input before cancelling
+    pub confirm_discard: bool,
+    /// Area the grid was last rendered into, used for mouse hit-testing
+    pub grid_area: Cell<Rect>,
+    /// Eyedropper cursor, relative to the modal's top-left corner
+    pub eyedropper: Position,
+    /// Area the modal was last rendered into
+    pub modal_area: Cell<Rect>,
+    /// Columns and rows the modal is shifted from the center, clamped on
+    /// render so it stays fully on-screen
+    pub modal_offset: Cell<(i16, i16)>,
+    /// Color under the eyedropper cursor on the last render
+    pub eyedropper_sample: Cell<Option<Color>>,
+    /// First grid row shown, once the palette has more rows than fit
+    pub grid_scroll: Cell<usize>,
+    /// Gradient being built; replaces the tints and shades once both ends are marked
+    pub gradient: Gradient,
+    /// Shortcut keys, shown in the hint bar and read by the key handler
+    pub keymap: KeyMap,
+    /// Name prefix typed in search mode; swatches whose nearest name doesn't
+    /// start with it are dimmed
+    pub search: String,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// Maximum number of colors kept in the recent-colors strip
+pub const RECENT_CAPACITY: usize = 8;
+
+/// Maximum number of pinned colors, as many as fit the favorites strip
+pub const FAVORITES_CAPACITY: usize = 16;
+
+/// Names of the RGB channels, indexed by [`ColorPickerWidget::nudge_channel`]
+const CHANNEL_NAMES: [&str; 3] = ["Red", "Green", "Blue"];
+
+/// Lightness change per key press, in percentage points
+pub const LIGHTNESS_STEP: i8 = 5;
+
+/// Number of tints, and separately of shades, derived from the base color
+pub const SHADE_STEPS: usize = 5;
+
+/// Smallest modal that still fits the banner, three palette rows, the shades,
+/// input, readouts and buttons: 3 + 8 + 3 + 3 + 5 + 4 rows inside the border
+/// and margin. On a shorter terminal, such as 80x24, the modal is clamped to
+/// the terminal's 24 rows and `create_layout` drops sections to make room.
+const MODAL_MIN_SIZE: (u16, u16) = (44, 30);
+
+/// Below this terminal size the modal is replaced by a short notice
+const TERMINAL_MIN_SIZE: (u16, u16) = (20, 10);
+
+/// Palette height, borders included, of a single grid row: the compact palette
+const COMPACT_PALETTE_HEIGHT: u16 = GRID_ROW_HEIGHT + 2;
+
+/// Palettes shorter than this show the compact layout, since fewer than three
+/// grid rows would fit
+const FULL_PALETTE_HEIGHT: u16 = 3 * GRID_ROW_HEIGHT + 2;
+
+/// Narrowest swatch in the compact palette before it scrolls sideways
+const COMPACT_SWATCH_WIDTH: u16 = 3;
+
+/// Smallest height of a grid row before the grid scrolls instead of squeezing
+const GRID_ROW_HEIGHT: u16 = 2;
+
+/// How long a toast stays on screen
+pub const TOAST_DURATION: Duration = Duration::from_millis(1500);
+
+/// Smallest ΔE most people can see; smaller edits are flagged as invisible
+const JUST_NOTICEABLE: f64 = 2.3;
+
+/// How long the input border stays red after Enter rejects it
+const REJECT_FLASH: Duration = Duration::from_millis(600);
+
+/// Transient message shown at the bottom of the modal
+#[derive(Debug)]
+pub struct Toast {
+    pub message: String,
+    pub shown_at: Instant,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum Focus {
+    #[default]
     Grid,
+    Sliders,
+    /// HSL gauges below the palette
+    Hsl,
+    Shades,
+    Recent,
+    Favorites,
     Input,
     Apply,
     Cancel,
+    /// Free cursor over the whole modal that samples the color under it
+    Eyedropper,
+    /// Typing a name prefix to filter the grid
+    Search,
 }
 
-impl Default for Focus {
-    fn default() -> Self {
-        Self::Grid
+impl ColorPickerWidget {
+    /// Use a custom palette, laid out in a roughly square grid
+    pub fn with_palette(mut self, colors: Vec<Color>) -> Self {
+        self.set_palette(colors);
+        self
     }
-}
 
-impl ColorPickerWidget {
-    pub fn focus_next(&mut self) {
-        self.focus = match self.focus {
-            Focus::Grid => Focus::Input,
-            Focus::Input => Focus::Apply,
-            Focus::Apply => Focus::Cancel,
-            Focus::Cancel => Focus::Grid,
+    /// Start with the grid cursor on the swatch closest to `color`; call after
+    /// [`Self::with_palette`] so the right palette is searched
+    pub fn with_initial_color(mut self, color: Color) -> Self {
+        self.select_nearest(color);
+        self
+    }
+
+    /// Modal width and height as percentages of the terminal area
+    pub fn with_modal_size(mut self, percent_x: u16, percent_y: u16) -> Self {
+        self.modal_size = (percent_x.clamp(1, 100), percent_y.clamp(1, 100));
+        self
+    }
+
+    /// Move the grid cursor to the swatch closest to `color` and prefill the
+    /// input with that swatch
+    pub fn select_nearest(&mut self, color: Color) {
+        self.grid_index = self.index_of_nearest(color);
+        if let Some(swatch) = self.selected_color() {
+            self.color_input.set_color(swatch);
+        }
+    }
+
+    /// Grid position of the swatch closest to `color` by distance in RGB space.
+    /// Indexed palettes only match exactly; anything else lands on `(0, 0)`.
+    pub fn index_of_nearest(&self, color: Color) -> (usize, usize) {
+        let (_, cols) = self.grid_dimensions;
+        let distance = |swatch: Color| match (rgb_components(color), rgb_components(swatch)) {
+            (Some(rgb), Some(swatch)) => rgb_distance(rgb, swatch),
+            _ if swatch == color => 0,
+            _ => i32::MAX,
         };
+
+        self.colors
+            .iter()
+            .map(|&swatch| self.adjust(swatch))
+            .enumerate()
+            .min_by_key(|&(_, swatch)| distance(swatch))
+            .map_or((0, 0), |(i, _)| (i / cols.max(1), i % cols.max(1)))
+    }
+
+    pub fn focus_next(&mut self) {
+        self.step_focus(1);
     }
 
     pub fn focus_prev(&mut self) {
-        self.focus = match self.focus {
-            Focus::Grid => Focus::Cancel,
-            Focus::Input => Focus::Grid,
-            Focus::Apply => Focus::Input,
-            Focus::Cancel => Focus::Apply,
+        self.step_focus(-1);
+    }
+
+    /// Sections Tab cycles through, in order, built from what's on screen.
+    /// Hidden panels and empty strips are skipped so no Tab press lands on
+    /// nothing. The eyedropper and search sit outside the cycle.
+    pub fn focus_order(&self) -> Vec<Focus> {
+        let sections = [
+            (self.palette_focus(), true),
+            (Focus::Hsl, self.show_hsl),
+            (Focus::Shades, !self.shade_strip().is_empty()),
+            (Focus::Recent, !self.recent.is_empty()),
+            (Focus::Favorites, !self.favorites.is_empty()),
+            (Focus::Input, true),
+            (Focus::Apply, true),
+            (Focus::Cancel, true),
+        ];
+
+        sections
+            .into_iter()
+            .filter_map(|(focus, shown)| shown.then_some(focus))
+            .collect()
+    }
+
+    /// Move `delta` places through [`Self::focus_order`], wrapping at either
+    /// end. From outside the cycle, forward lands on the first section and
+    /// backward on the last.
+    fn step_focus(&mut self, delta: isize) {
+        let order = self.focus_order();
+        let len = order.len() as isize;
+
+        let next = match order.iter().position(|&focus| focus == self.focus) {
+            Some(index) => (index as isize + delta).rem_euclid(len),
+            None if delta > 0 => 0,
+            None => len - 1,
         };
+        self.focus = order[next as usize];
     }
 
-    pub fn selected_color(&self) -> Option<Color> {
+    /// Enter eyedropper mode with the cursor centered, or leave it again
+    pub fn toggle_eyedropper(&mut self) {
+        if self.focus == Focus::Eyedropper {
+            self.focus = self.palette_focus();
+            return;
+        }
+
+        let area = self.modal_area.get();
+        self.eyedropper = Position::new(area.width / 2, area.height / 2);
+        self.focus = Focus::Eyedropper;
+    }
+
+    /// Shift the modal; it's pulled back on-screen on the next render
+    pub fn move_modal(&mut self, dx: i16, dy: i16) {
+        let (x, y) = self.modal_offset.get();
+        self.modal_offset
+            .set((x.saturating_add(dx), y.saturating_add(dy)));
+    }
+
+    /// Move the eyedropper cursor, keeping it inside the modal
+    pub fn move_eyedropper(&mut self, dx: i16, dy: i16) {
+        let area = self.modal_area.get();
+        let step = |value: u16, delta: i16, length: u16| {
+            value
+                .saturating_add_signed(delta)
+                .min(length.saturating_sub(1))
+        };
+
+        self.eyedropper = Position::new(
+            step(self.eyedropper.x, dx, area.width),
+            step(self.eyedropper.y, dy, area.height),
+        );
+    }
+
+    /// Start typing a search query, swapping the sliders back to the grid
+    pub fn start_search(&mut self) {
+        self.show_sliders = false;
+        self.focus = Focus::Search;
+    }
+
+    /// Drop the query and return to the full grid
+    pub fn clear_search(&mut self) {
+        self.search.clear();
+        self.focus = Focus::Grid;
+    }
+
+    /// True when `color`'s nearest name starts with the query, or there's no query
+    pub fn matches_search(&self, color: Color) -> bool {
+        // Indexed swatches go by the name of their usual xterm value
+        let color = approximate_rgb(color).map_or(color, |(r, g, b)| Color::Rgb(r, g, b));
+        nearest_named(color).starts_with(&self.search)
+    }
+
+    /// Move the grid selection to the first color matching the query.
+    /// Returns false, leaving the selection alone, if nothing matches.
+    pub fn snap_to_search(&mut self) -> bool {
         let (_, cols) = self.grid_dimensions;
-        let idx = self.grid_index.0 * cols + self.grid_index.1;
-        self.colors.get(idx).copied()
-    }
-
-    pub fn generate_colors() -> (Vec<Color>, (usize, usize)) {
-        let hues = [
-            &material::RED,
-            &material::PINK,
-            &material::PURPLE,
-            &material::DEEP_PURPLE,
-            &material::INDIGO,
-            &material::BLUE,
-            &material::LIGHT_BLUE,
-            &material::CYAN,
-            &material::TEAL,
-            &material::GREEN,
-            &material::LIGHT_GREEN,
-            &material::LIME,
-            &material::YELLOW,
-            &material::AMBER,
-            &material::ORANGE,
-            &material::DEEP_ORANGE,
-        ];
+        let Some(idx) = self
+            .colors
+            .iter()
+            .position(|&c| self.matches_search(self.adjust(c)))
+        else {
+            return false;
+        };
+
+        self.grid_index = (idx / cols, idx % cols);
+        true
+    }
+
+    /// True when the input holds something other than the grid selection,
+    /// such as a typed color that cancelling would throw away. Indexed swatches
+    /// show in the input as their RGB approximation, which doesn't count.
+    pub fn has_unsaved_input(&self) -> bool {
+        !self.color_input.input.is_empty()
+            && self.color_input.color().and_then(approximate_rgb)
+                != self.selected_color().and_then(approximate_rgb)
+    }
+
+    /// Hint bar text for the current focus, e.g. `Grid — arrows: move, tab: next`
+    pub fn focus_hint(&self) -> String {
+        let keys = &self.keymap;
+        let next = keys.focus_next;
+
+        match self.focus {
+            Focus::Grid => format!(
+                "Grid — arrows: move, {}: apply, {}: search, {}: invert, {}: complement, {next}: next",
+                keys.confirm, keys.search, keys.invert, keys.complement
+            ),
+            Focus::Sliders => format!(
+                "Sliders — ↑↓: channel, ←→: adjust, Shift: ×10, {}: lock",
+                keys.lock_channel
+            ),
+            Focus::Hsl => format!(
+                "HSL — ↑↓: component, ←→: adjust, Shift: ×10, {}: lock, {}: hide",
+                keys.lock_channel, keys.toggle_hsl
+            ),
+            Focus::Shades => format!(
+                "Shades — ←→: pick, {}: gradient, {}/{}: stops",
+                keys.mark_gradient, keys.more_gradient_stops, keys.fewer_gradient_stops
+            ),
+            Focus::Recent => format!("Recent — ←→: pick, {}: pin", keys.toggle_favorite),
+            Focus::Favorites => format!("Favorites — ←→: pick, {}: unpin", keys.toggle_favorite),
+            Focus::Input => format!(
+                "Input — type a color, {}: mode, {}: invert, {}: complement, {next}: next",
+                keys.cycle_input_mode, keys.invert, keys.complement
+            ),
+            Focus::Apply => format!("Apply — {}: apply, →: cancel, {next}: next", keys.confirm),
+            Focus::Cancel => format!("Cancel — {}: cancel, ←: apply, {next}: next", keys.confirm),
+            Focus::Eyedropper => format!(
+                "Eyedropper — arrows: move, {}: sample, {}: exit",
+                keys.confirm, keys.toggle_eyedropper
+            ),
+            Focus::Search => "Search — type a name, enter: keep, esc: clear".to_string(),
+        }
+    }
+
+    /// Focus target for whichever view currently occupies the palette area
+    pub fn palette_focus(&self) -> Focus {
+        if self.show_sliders {
+            Focus::Sliders
+        } else {
+            Focus::Grid
+        }
+    }
+
+    /// Swap between the palette grid and the HSV sliders, seeding the sliders
+    /// from the current color
+    pub fn toggle_sliders(&mut self) {
+        self.show_sliders = !self.show_sliders;
+
+        if self.show_sliders
+            && let Some(color) = self.color_input.color().or_else(|| self.selected_color())
+        {
+            self.sliders.set_color(color);
+        }
+
+        if matches!(self.focus, Focus::Grid | Focus::Sliders) {
+            self.focus = self.palette_focus();
+        }
+    }
+
+    pub fn selected_color(&self) -> Option<Color> {
+        let (row, col) = self.grid_index;
+        self.get_color_at(row, col)
+    }
+
+    /// Pick the next or previous RGB channel for nudging
+    pub fn cycle_nudge_channel(&mut self, delta: isize) {
+        self.nudge_channel = (self.nudge_channel as isize + delta).rem_euclid(3) as usize;
+        self.show_toast(format!("Nudging {}", CHANNEL_NAMES[self.nudge_channel]));
+    }
+
+    /// Step the selected RGB channel of the current color by `delta`
+    pub fn nudge(&mut self, delta: i16) {
+        let channel = self.nudge_channel;
+        let edited = self.edit_color(|rgb| {
+            let mut channels = [rgb.0, rgb.1, rgb.2];
+            channels[channel] = (i16::from(channels[channel]) + delta).clamp(0, 255) as u8;
+            (channels[0], channels[1], channels[2])
+        });
+
+        if let Some(rgb) = edited {
+            let value = [rgb.0, rgb.1, rgb.2][channel];
+            self.show_toast(format!("{} {value}", CHANNEL_NAMES[channel]));
+        }
+    }
+
+    /// Replace the current color with its RGB inverse
+    pub fn invert(&mut self) {
+        self.edit_color(|(r, g, b)| (255 - r, 255 - g, 255 - b));
+    }
+
+    /// Replace the current color with its complement, the hue opposite it on
+    /// the color wheel at the same saturation and lightness
+    pub fn complement(&mut self) {
+        self.edit_color(|(r, g, b)| {
+            let (h, s, l) = rgb_to_hsl(r, g, b);
+            hsl_to_rgb((h + 180) % 360, s, l)
+        });
+    }
+
+    /// Run `edit` on the current color and write the result to the input.
+    /// Editing a swatch moves focus to the input so Apply picks up the edited
+    /// color rather than the swatch. Returns the new color, or `None` if there
+    /// was no RGB color to edit.
+    fn edit_color(
+        &mut self,
+        edit: impl FnOnce((u8, u8, u8)) -> (u8, u8, u8),
+    ) -> Option<(u8, u8, u8)> {
+        let rgb = self.current_color().and_then(rgb_components)?;
+
+        let (r, g, b) = edit(rgb);
+        self.begin_edit();
+        self.color_input.set_color(Color::Rgb(r, g, b));
+
+        if matches!(self.focus, Focus::Grid | Focus::Search) {
+            self.focus = Focus::Input;
+        }
+        Some((r, g, b))
+    }
+
+    /// Switch between the grid and the histogram, if the palette has weights
+    pub fn toggle_histogram(&mut self) {
+        if self.weights.is_none() {
+            self.show_toast("No weights for this palette");
+            return;
+        }
+        self.show_histogram = !self.show_histogram;
+    }
+
+    /// True when the histogram takes the grid's place
+    pub fn histogram_active(&self) -> bool {
+        self.show_histogram && !self.show_sliders && self.weights.is_some()
+    }
+
+    /// Show or hide the HSL gauges, moving focus off them when they go
+    pub fn toggle_hsl(&mut self) {
+        self.show_hsl = !self.show_hsl;
+        self.sync_hsl();
+
+        if !self.show_hsl && self.focus == Focus::Hsl {
+            self.focus = self.palette_focus();
+        }
+    }
+
+    /// Point the HSL gauges at the current color, keeping their components
+    /// if they already make it
+    pub fn sync_hsl(&mut self) {
+        if let Some(color) = self.current_color() {
+            self.hsl.sync(color);
+        }
+    }
+
+    /// Shift the lightness of the whole palette by `delta` percentage points
+    pub fn adjust_lightness(&mut self, delta: i8) {
+        self.lightness = self.lightness.saturating_add(delta).clamp(-100, 100);
+    }
+
+    /// `color` with the palette lightness shift applied; indexed colors are
+    /// left alone since the terminal decides what they look like
+    fn adjust(&self, color: Color) -> Color {
+        let Some((r, g, b)) = rgb_components(color).filter(|_| self.lightness != 0) else {
+            return color;
+        };
+
+        let (h, s, l) = rgb_to_hsl(r, g, b);
+        let l = (i16::from(l) + i16::from(self.lightness)).clamp(0, 100) as u8;
+        let (r, g, b) = hsl_to_rgb(h, s, l);
+        Color::Rgb(r, g, b)
+    }
+
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Time left before the toast expires, or `None` without one
+    pub fn toast_remaining(&self) -> Option<Duration> {
+        self.toast
+            .as_ref()
+            .map(|toast| TOAST_DURATION.saturating_sub(toast.shown_at.elapsed()))
+    }
+
+    /// Clear the toast once it has been shown long enough
+    pub fn expire_toast(&mut self) {
+        if self.toast_remaining() == Some(Duration::ZERO) {
+            self.toast = None;
+        }
+    }
+
+    /// After an edit, move on to Apply if auto-advance is on and the input now
+    /// holds a complete hex color. Decimal modes never advance, since there's
+    /// no telling when their last number is finished.
+    pub fn advance_if_complete(&mut self) {
+        if self.auto_advance
+            && self.color_input.hex_progress() == Some((6, 6))
+            && self.color_input.is_valid()
+        {
+            self.focus = Focus::Apply;
+        }
+    }
+
+    /// Fill the input with a picked color, which starts a fresh comparison
+    pub fn load_color(&mut self, color: Color) {
+        self.color_input.set_color(color);
+        self.original = None;
+    }
+
+    /// Remember the current color as the original before changing it, unless
+    /// an edit is already under way
+    pub fn begin_edit(&mut self) {
+        if self.original.is_none() {
+            self.original = self.current_color();
+        }
+    }
+
+    /// Clear the input and move the grid cursor back to the first swatch
+    pub fn reset(&mut self) {
+        self.color_input.clear();
+        self.original = None;
+        self.grid_index = (0, 0);
+        self.grid_scroll.set(0);
+        self.show_toast("Reset");
+    }
+
+    /// Enter in the input: move on to Apply once it holds a color, otherwise
+    /// flash its border to show why nothing happened
+    pub fn commit_input(&mut self) {
+        if self.color_input.is_valid() {
+            self.focus = Focus::Apply;
+        } else {
+            self.rejected_at = Some(Instant::now());
+        }
+    }
+
+    /// Whether the blinking input cursor is in its on half. Drawn in software,
+    /// since many terminals ignore the blink attribute.
+    fn cursor_visible(&self) -> bool {
+        // Two ticks on, two off
+        let ticks = self.animation_phase.wrapping_sub(self.blink_start);
+        !(self.animations && self.blink) || (ticks / 2).is_multiple_of(2)
+    }
+
+    /// True while the input border is flashing after a rejected Enter
+    fn input_rejected(&self) -> bool {
+        self.rejected_at
+            .is_some_and(|at| at.elapsed() < REJECT_FLASH)
+    }
+
+    /// The color the picker is on right now: the input if it holds a color,
+    /// otherwise the selection in the grid or sliders. Embedders can read this
+    /// each frame to follow the selection live rather than waiting for Apply.
+    pub fn current_color(&self) -> Option<Color> {
+        self.color_input.color().or_else(|| self.base_color())
+    }
+
+    /// Color picked in whichever view occupies the palette area
+    pub fn base_color(&self) -> Option<Color> {
+        if self.show_sliders {
+            Some(self.sliders.color())
+        } else {
+            self.selected_color()
+        }
+    }
+
+    /// Shades toward black then tints toward white of the base color, darkest first
+    pub fn shades(&self) -> Vec<Color> {
+        let Some(Color::Rgb(r, g, b)) = self.base_color() else {
+            return Vec::new();
+        };
+
+        let steps = SHADE_STEPS + 1;
+        let toward = |target: (u8, u8, u8), i: usize| {
+            let (r, g, b) = mix_rgb((r, g, b), target, i as f64 / steps as f64);
+            Color::Rgb(r, g, b)
+        };
+
+        let shades = (1..steps).rev().map(|i| toward((0, 0, 0), i));
+        let tints = (1..steps).map(|i| toward((255, 255, 255), i));
+        shades.chain(tints).collect()
+    }
+
+    /// Colors in the shades strip: the gradient stops once it's complete,
+    /// otherwise the tints and shades
+    pub fn shade_strip(&self) -> Vec<Color> {
+        if self.gradient.is_complete() {
+            self.gradient.stops()
+        } else {
+            self.shades()
+        }
+    }
+
+    pub fn selected_shade(&self) -> Option<Color> {
+        self.shade_strip().get(self.shade_index).copied()
+    }
+
+    /// Mark the current color as a gradient end
+    pub fn mark_gradient(&mut self) {
+        let Some(color) = self.current_color() else {
+            return;
+        };
+
+        let status = self.gradient.mark(color);
+        self.show_toast(status);
+        self.shade_index = 0;
+    }
 
-        let accents = [50, 100, 200, 300, 400, 500, 600, 700, 800, 900];
-        let mut colors = Vec::with_capacity(hues.len() * accents.len());
+    pub fn selected_recent(&self) -> Option<Color> {
+        self.recent.get(self.recent_index).copied()
+    }
+
+    /// Lay the built-in hue palettes out in `order`, keeping the same swatch selected
+    pub fn set_palette_order(&mut self, order: PaletteOrder) {
+        self.palette_order = order;
 
-        for &accent in &accents {
-            for hue in &hues {
-                let color = Self::get_color_for_accent(hue, accent);
-                colors.push(color);
+        if let Some(palette) = self.palette {
+            let selected = self.selected_color();
+            self.set_builtin_palette(palette);
+            if let Some(color) = selected {
+                self.grid_index = self.index_of_nearest(color);
             }
         }
+    }
+
+    /// Fill the grid with a harmony of the current color, or switch an
+    /// already shown harmony to the next scheme from the same base
+    pub fn cycle_harmony(&mut self) {
+        let (harmony, base) = match self.harmony {
+            Some((harmony, base)) => (harmony.next(), base),
+            None => {
+                let Some(base) = self.current_color().and_then(approximate_rgb) else {
+                    return;
+                };
+                (Harmony::default(), base)
+            }
+        };
+
+        let (colors, dimensions) = harmony.colors(base);
+        self.set_palette_grid(colors, dimensions);
+        self.harmony = Some((harmony, base));
+    }
+
+    /// Show one of the built-in palettes
+    pub fn set_builtin_palette(&mut self, palette: Palette) {
+        let (colors, dimensions) = palette.colors_in(self.palette_order);
+        self.set_palette_grid(colors, dimensions);
+        self.palette = Some(palette);
+    }
+
+    /// Switch to the next built-in palette; a custom palette is followed by material
+    pub fn cycle_palette(&mut self) {
+        let next = self.palette.map_or(Palette::default(), Palette::next);
+        self.set_builtin_palette(next);
+    }
+
+    /// Select a random swatch anywhere in the palette
+    pub fn select_random(&mut self) {
+        let (_, cols) = self.grid_dimensions;
+        if self.colors.is_empty() {
+            return;
+        }
+
+        let idx = fastrand::usize(..self.colors.len());
+        self.grid_index = (idx / cols, idx % cols);
+    }
+
+    pub fn selected_favorite(&self) -> Option<Color> {
+        self.favorites.get(self.favorite_index).copied()
+    }
+
+    /// Pin the current color, or unpin it if it's already a favorite. While
+    /// the favorites have focus this acts on the selected favorite.
+    pub fn toggle_favorite(&mut self) {
+        let color = if self.focus == Focus::Favorites {
+            self.selected_favorite()
+        } else {
+            self.current_color()
+        };
+        let Some(color) = color else {
+            return;
+        };
 
-        (colors, (accents.len(), hues.len()))
+        if let Some(index) = self.favorites.iter().position(|&c| c == color) {
+            self.favorites.remove(index);
+            self.favorite_index = self
+                .favorite_index
+                .min(self.favorites.len().saturating_sub(1));
+            self.show_toast("Unpinned");
+        } else if self.favorites.len() < FAVORITES_CAPACITY {
+            self.favorites.push(color);
+            self.show_toast("Saved to favorites");
+        } else {
+            self.show_toast("Favorites are full");
+        }
     }
 
-    fn get_color_for_accent(hue: &material::AccentedPalette, accent: u16) -> Color {
-        match accent {
-            50 => hue.c50,
-            100 => hue.c100,
-            200 => hue.c200,
-            300 => hue.c300,
-            400 => hue.c400,
-            500 => hue.c500,
-            600 => hue.c600,
-            700 => hue.c700,
-            800 => hue.c800,
-            900 => hue.c900,
-            _ => hue.c500,
+    /// Add the selected swatch, or the current color outside the grid, to the
+    /// selection. Takes it back out if it's already there.
+    pub fn toggle_selection(&mut self) {
+        let color = if self.focus == Focus::Grid {
+            self.selected_color()
+        } else {
+            self.current_color()
+        };
+        let Some(color) = color else {
+            return;
+        };
+
+        if let Some(index) = self.selection.iter().position(|&c| c == color) {
+            self.selection.remove(index);
+        } else {
+            self.selection.push(color);
         }
     }
 
+    /// Move `color` to the front of the recent colors, dropping the oldest beyond capacity
+    pub fn push_recent(&mut self, color: Color) {
+        self.recent.retain(|&c| c != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(RECENT_CAPACITY);
+        self.recent_index = 0;
+    }
+
+    /// Replace the palette, laying it out in a roughly square grid
+    pub fn set_palette(&mut self, colors: Vec<Color>) {
+        let dimensions = palette::grid_dimensions(colors.len());
+        self.set_palette_grid(colors, dimensions);
+    }
+
+    /// Use a custom palette along with each color's weight
+    pub fn set_weighted_palette(&mut self, colors: Vec<(Color, f64)>) {
+        let (colors, weights) = colors.into_iter().unzip();
+        self.set_palette(colors);
+        self.weights = Some(weights);
+    }
+
+    /// Replace the palette with a fixed `(rows, cols)` layout
+    pub fn set_palette_grid(&mut self, colors: Vec<Color>, dimensions: (usize, usize)) {
+        self.palette = None;
+        self.weights = None;
+        self.harmony = None;
+        self.grid_dimensions = dimensions;
+        self.colors = colors;
+        self.grid_index = (0, 0);
+        self.grid_scroll.set(0);
+    }
+
+    /// `RRGGBB` for RGB colors. Indexed and named colors have no exact hex, so
+    /// this is `None` for them; see [`ColorInput::set_color`] for what the input
+    /// shows instead.
     pub fn color_to_hex(color: Color) -> Option<String> {
         match color {
-            Color::Rgb(r, g, b) => Some(format!("{r:02X}{g:02X}{b:02X}")),
+            Color::Rgb(r, g, b) => Some(rgb_to_hex_bare(r, g, b)),
             _ => None,
         }
     }
+
+    /// ANSI palette index of an indexed color
+    pub fn color_to_ansi_index(color: Color) -> Option<u8> {
+        match color {
+            Color::Indexed(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    pub fn color_to_hexa(color: Color, alpha: u8) -> Option<String> {
+        Self::color_to_hex(color).map(|hex| format!("{hex}{alpha:02X}"))
+    }
 }
 
 impl Default for ColorPickerWidget {
     fn default() -> Self {
-        let (colors, grid_dimensions) = Self::generate_colors();
+        let (colors, grid_dimensions) = Palette::default().colors();
 
         Self {
             modal_state: false,
             grid_index: (0, 0),
             color_input: ColorInput::default(),
             focus: Focus::default(),
+            start_on_input: false,
+            auto_advance: false,
             colors,
             grid_dimensions,
+            palette: Some(Palette::default()),
+            palette_order: PaletteOrder::default(),
+            weights: None,
+            show_histogram: false,
+            harmony: None,
+            recent: Vec::with_capacity(RECENT_CAPACITY),
+            recent_index: 0,
+            favorites: Vec::new(),
+            favorite_index: 0,
+            selection: Vec::new(),
+            shade_index: 0,
+            alpha: None,
+            toast: None,
+            original: None,
+            rejected_at: None,
+            modal_size: (50, 50),
+            sliders: HsvSliders::default(),
+            show_sliders: false,
+            hsl: HslState::default(),
+            show_hsl: false,
+            swatch_width: None,
+            show_labels: false,
+            grid_gaps: false,
+            lightness: 0,
+            color_support: ColorSupport::default(),
+            theme: Theme::default(),
+            nudge_channel: 0,
+            compact: false,
+            compact_active: Cell::new(false),
+            animations: true,
+            animation_phase: 0,
+            blink: true,
+            blink_start: 0,
+            vision: VisionMode::default(),
+            escape_background: false,
+            locked: false,
+            confirm_discard: false,
+            grid_area: Cell::new(Rect::default()),
+            eyedropper: Position::default(),
+            modal_area: Cell::new(Rect::default()),
+            modal_offset: Cell::new((0, 0)),
+            eyedropper_sample: Cell::new(None),
+            grid_scroll: Cell::new(0),
+            gradient: Gradient::default(),
+            keymap: KeyMap::default(),
+            search: String::new(),
         }
     }
 }
@@ -138,40 +926,215 @@ impl Widget for &ColorPickerWidget {
             return;
         }
 
-        let modal_area = create_modal_area(area, 50, 50);
+        if area.width < TERMINAL_MIN_SIZE.0 || area.height < TERMINAL_MIN_SIZE.1 {
+            render_too_small(area, buf);
+            return;
+        }
+
+        let (percent_x, percent_y) = self.modal_size;
+        let (modal_area, offset) =
+            create_modal_area(area, percent_x, percent_y, self.modal_offset.get());
+        self.modal_offset.set(offset);
         Clear.render(modal_area, buf);
 
+        let mut title = "Color Picker".to_string();
+        if self.vision != VisionMode::Normal {
+            title.push_str(&format!(" [{}]", self.vision.label()));
+        }
+        if self.locked {
+            title.push_str(" [Locked]");
+        }
+
         let block = Block::default()
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
-            .title("Color Picker")
-            .style(Styles::modal_background());
+            .title(title)
+            .style(self.theme.modal_background());
 
         block.clone().render(modal_area, buf);
-
         let layout = self.create_layout(block.inner(modal_area));
 
+        self.render_banner(layout.banner, buf);
         self.render_color_palette(layout.palette, buf);
+        if self.show_hsl {
+            self.render_hsl_gauges(layout.hsl, buf);
+        }
+        let shades_title = if self.gradient.is_complete() {
+            self.gradient.label()
+        } else {
+            "Tints & Shades".to_string()
+        };
+        self.render_swatch_strip(
+            layout.shades,
+            &shades_title,
+            &self.shade_strip(),
+            self.focus == Focus::Shades,
+            self.shade_index,
+            buf,
+        );
+        self.render_swatch_strip(
+            layout.recent,
+            "Recent",
+            &self.recent,
+            self.focus == Focus::Recent,
+            self.recent_index,
+            buf,
+        );
+        self.render_swatch_strip(
+            layout.favorites,
+            "Favorites",
+            &self.favorites,
+            self.focus == Focus::Favorites,
+            self.favorite_index,
+            buf,
+        );
+        if !self.selection.is_empty() {
+            let title = format!("Selection ({})", self.selection.len());
+            self.render_swatch_strip(layout.selection, &title, &self.selection, false, 0, buf);
+        }
         self.render_text_inputs(layout.input, buf);
+        self.render_breakdown(layout.breakdown, buf);
+        self.render_cmyk(layout.cmyk, buf);
+        self.render_escape(layout.escape, buf);
+        self.render_contrast(layout.contrast, buf);
+        if self.original.is_some() {
+            self.render_comparison(layout.comparison, buf);
+        }
         self.render_modal_buttons(&layout.buttons, buf);
+        self.render_hints(layout.hints, buf);
+
+        self.modal_area.set(modal_area);
+        if self.focus == Focus::Eyedropper {
+            self.render_eyedropper(modal_area, buf);
+        }
+        if let Some(toast) = &self.toast {
+            render_toast(modal_area, &toast.message, &self.theme, buf);
+        }
+        if self.confirm_discard {
+            render_confirm_discard(modal_area, &self.theme, buf);
+        }
+
+        // Last, so the eyedropper still samples the true colors
+        self.color_support.quantize_buffer(modal_area, buf);
     }
 }
 
+/// Selection marker drawn over a swatch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    None,
+    /// Selected, but its section doesn't have focus
+    Selected,
+    /// Selected in the focused section
+    Focused,
+}
+
 struct ModalLayout {
+    banner: Rect,
     palette: Rect,
+    hsl: Rect,
+    shades: Rect,
+    recent: Rect,
+    favorites: Rect,
+    selection: Rect,
     input: Rect,
+    breakdown: Rect,
+    cmyk: Rect,
+    escape: Rect,
+    contrast: Rect,
+    comparison: Rect,
     buttons: [Rect; 3],
+    hints: Rect,
 }
 
 impl ColorPickerWidget {
     fn create_layout(&self, area: Rect) -> ModalLayout {
-        let popup_layout = Layout::vertical([
-            Constraint::Percentage(85),
-            Constraint::Length(3),
-            Constraint::Length(3),
-        ])
+        // Sections claim rows in priority order and are left out once they no
+        // longer fit, so a short modal drops the readouts and hints before the
+        // input and buttons lose space. Whatever is left grows the palette.
+        let mut budget = area.height.saturating_sub(2);
+        let mut take = |rows: u16| {
+            if rows > budget {
+                return 0;
+            }
+            budget -= rows;
+            rows
+        };
+        let strip = |shown: bool| if shown { 3 } else { 0 };
+
+        let input_rows = take(3);
+        let buttons_rows = take(4);
+        // One row of two-line swatches, shown as the compact palette
+        let palette_rows = take(COMPACT_PALETTE_HEIGHT);
+        let banner_rows = take(3);
+        let hsl_rows = take(if self.show_hsl { 5 } else { 0 });
+        // Empty strips take no room
+        let shades_rows = take(strip(!self.shade_strip().is_empty()));
+        let selection_rows = take(strip(!self.selection.is_empty()));
+        let recent_rows = take(strip(!self.recent.is_empty()));
+        let favorites_rows = take(strip(!self.favorites.is_empty()));
+        // The comparison only appears once the color has been edited
+        let comparison_rows = take(if self.original.is_some() { 4 } else { 0 });
+        let full_palette_rows = if self.compact {
+            0
+        } else {
+            take(FULL_PALETTE_HEIGHT - COMPACT_PALETTE_HEIGHT)
+        };
+        let [
+            hints_rows,
+            breakdown_rows,
+            contrast_rows,
+            cmyk_rows,
+            escape_rows,
+        ] = [1; 5].map(&mut take);
+        let palette_rows = if self.compact || palette_rows == 0 {
+            palette_rows
+        } else {
+            palette_rows + full_palette_rows + budget
+        };
+        // Decided from the palette's actual height, so the modal stays
+        // compact only while fewer than three grid rows would fit
+        self.compact_active
+            .set(self.compact || palette_rows < FULL_PALETTE_HEIGHT);
+
+        let [
+            banner,
+            palette,
+            hsl,
+            shades,
+            recent,
+            favorites,
+            selection,
+            input,
+            breakdown,
+            cmyk,
+            escape,
+            contrast,
+            comparison,
+            buttons,
+            hints,
+        ] = Layout::vertical(
+            [
+                banner_rows,
+                palette_rows,
+                hsl_rows,
+                shades_rows,
+                recent_rows,
+                favorites_rows,
+                selection_rows,
+                input_rows,
+                breakdown_rows,
+                cmyk_rows,
+                escape_rows,
+                contrast_rows,
+                comparison_rows,
+                buttons_rows,
+                hints_rows,
+            ]
+            .map(Constraint::Length),
+        )
         .margin(1)
-        .split(area);
+        .areas(area);
 
         let buttons_layout = Layout::horizontal([
             Constraint::Length(15),
@@ -179,63 +1142,408 @@ impl ColorPickerWidget {
             Constraint::Length(15),
         ])
         .flex(Flex::End)
-        .split(popup_layout[2]);
+        .split(buttons);
 
         ModalLayout {
-            palette: popup_layout[0],
-            input: popup_layout[1],
+            banner,
+            palette,
+            hsl,
+            shades,
+            recent,
+            favorites,
+            selection,
+            input,
+            breakdown,
+            cmyk,
+            escape,
+            contrast,
+            comparison,
             buttons: [buttons_layout[0], buttons_layout[1], buttons_layout[2]],
+            hints,
         }
     }
 
     fn render_color_palette(&self, area: Rect, buf: &mut Buffer) {
-        let grid_block = Block::default()
+        let searching = self.focus == Focus::Search;
+        let focused = searching || self.focus == self.palette_focus();
+        let mut grid_block = Block::default()
             .borders(Borders::ALL)
-            .border_style(Styles::focus_border(self.focus == Focus::Grid));
+            .border_style(self.theme.focus_border(focused));
+
+        if searching || !self.search.is_empty() {
+            grid_block = grid_block.title(format!("/{}", self.search));
+        }
+        if self.compact_active.get() && !self.show_sliders {
+            let (rows, _) = self.grid_dimensions;
+            let shade = format!("{}/{rows}", self.grid_index.0 + 1);
+            grid_block = grid_block.title(Line::from(shade).right_aligned());
+        }
+        let label = match (self.palette, self.harmony) {
+            (Some(palette), _) => Some(palette.label()),
+            (None, Some((harmony, _))) => Some(harmony.label()),
+            (None, None) => None,
+        };
+        if let Some(label) = label
+            && !self.show_sliders
+        {
+            grid_block = grid_block.title(Line::from(label).right_aligned());
+        }
+        if self.lightness != 0 && !self.show_sliders {
+            let shift = format!("L{:+}%", self.lightness);
+            grid_block = grid_block.title(Line::from(shift).right_aligned());
+        }
 
         grid_block.clone().render(area, buf);
         let inner = grid_block.inner(area);
 
-        self.render_color_grid(inner, buf);
+        if self.show_sliders {
+            HsvSlidersWidget {
+                sliders: &self.sliders,
+                focused,
+                theme: self.theme,
+            }
+            .render(inner, buf);
+        } else if let Some(weights) = self.weights.as_ref().filter(|_| self.show_histogram) {
+            self.render_histogram(inner, weights, buf);
+        } else {
+            self.render_color_grid(inner, buf);
+        }
+    }
+
+    /// One bar per swatch, as long as its weight relative to the heaviest,
+    /// with the selected one marked
+    fn render_histogram(&self, area: Rect, weights: &[f64], buf: &mut Buffer) {
+        // Bars aren't laid out like the grid, so mouse clicks don't hit swatches
+        self.grid_area.set(Rect::default());
+        if area.is_empty() {
+            return;
+        }
+
+        let (_, cols) = self.grid_dimensions;
+        let selected = self.grid_index.0 * cols + self.grid_index.1;
+        let heaviest = weights.iter().copied().fold(0.0, f64::max);
+
+        // Scroll just enough to keep the selected bar on screen
+        let first = selected.saturating_sub(usize::from(area.height) - 1);
+        let rows = (first..self.colors.len()).zip(area.rows());
+
+        for (index, row) in rows {
+            let [marker, bar, share] = Layout::horizontal([
+                Constraint::Length(2),
+                Constraint::Fill(1),
+                Constraint::Length(7),
+            ])
+            .areas(row);
+
+            let weight = weights.get(index).copied().unwrap_or_default();
+            let ratio = if heaviest > 0.0 {
+                weight / heaviest
+            } else {
+                0.0
+            };
+            let bar = Rect {
+                width: ((f64::from(bar.width) * ratio).round() as u16).max(1),
+                ..bar
+            };
+
+            if let Some(color) = self.colors.get(index).map(|&color| self.adjust(color)) {
+                self.render_color_cell(bar, color, Marker::None, false, None, buf);
+            }
+            if index == selected {
+                let focused = matches!(self.focus, Focus::Grid | Focus::Search);
+                let style = self.theme.focus_border(focused);
+                Line::styled("▶", style).render(marker, buf);
+            }
+            Line::styled(format!("{:.1}%", weight * 100.0), self.theme.muted())
+                .right_aligned()
+                .render(share, buf);
+        }
+    }
+
+    /// The HSL gauges, following the current color even before they're stepped
+    fn render_hsl_gauges(&self, area: Rect, buf: &mut Buffer) {
+        let focused = self.focus == Focus::Hsl;
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("HSL")
+            .border_style(self.theme.focus_border(focused));
+
+        block.clone().render(area, buf);
+
+        let mut state = self.hsl.clone();
+        if let Some(color) = self.current_color() {
+            state.sync(color);
+        }
+        HslGaugesWidget {
+            state: &state,
+            focused,
+            theme: self.theme,
+        }
+        .render(block.inner(area), buf);
+    }
+
+    /// Sample the background under the cursor, then draw the cursor over it
+    fn render_eyedropper(&self, modal_area: Rect, buf: &mut Buffer) {
+        let position = Position::new(
+            modal_area.x + self.eyedropper.x,
+            modal_area.y + self.eyedropper.y,
+        );
+
+        let sample = buf
+            .cell(position)
+            .map(|cell| cell.bg)
+            .filter(|bg| matches!(bg, Color::Rgb(..)));
+        self.eyedropper_sample.set(sample);
+
+        if let Some(cell) = buf.cell_mut(position) {
+            cell.set_char('+');
+            cell.set_style(Style::default().fg(Color::White).bg(Color::Black));
+        }
+    }
+
+    /// A titled row of swatches, sized to fit the recent colors or the shades
+    fn render_swatch_strip(
+        &self,
+        area: Rect,
+        title: &str,
+        colors: &[Color],
+        focused: bool,
+        selected_index: usize,
+        buf: &mut Buffer,
+    ) {
+        let strip_block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(self.theme.focus_border(focused));
+
+        strip_block.clone().render(area, buf);
+        let inner = strip_block.inner(area);
+
+        let slot_count = RECENT_CAPACITY.max(colors.len());
+        let slot_constraints = vec![Constraint::Ratio(1, slot_count as u32); slot_count];
+        let slots = Layout::horizontal(slot_constraints).split(inner);
+
+        for (i, &color) in colors.iter().enumerate() {
+            let marker = if focused && i == selected_index {
+                Marker::Focused
+            } else {
+                Marker::None
+            };
+            self.render_color_cell(slots[i], color, marker, false, None, buf);
+        }
     }
 
     fn render_color_grid(&self, area: Rect, buf: &mut Buffer) {
-        let (rows, cols) = self.grid_dimensions;
-        let row_constraints = vec![Constraint::Ratio(1, rows as u32); rows];
-        let grid_layout = Layout::vertical(row_constraints).split(area);
+        let (rows, _) = self.grid_dimensions;
+        let visible = self.visible_rows(area);
 
-        for row in 0..rows {
-            let col_constraints = vec![Constraint::Ratio(1, cols as u32); cols];
-            let row_layout = Layout::horizontal(col_constraints).split(grid_layout[row]);
+        // Give up a column for the scrollbar once the rows no longer all fit
+        let scrollable = visible < rows && !self.compact_active.get();
+        let scrollbar_area = scrollable.then(|| {
+            let [_, scrollbar] =
+                Layout::horizontal([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+            scrollbar
+        });
+        let area = Rect {
+            width: area.width - scrollbar_area.map_or(0, |a| a.width),
+            ..area
+        };
+
+        self.grid_area.set(area);
+        self.scroll_to_selection(visible);
 
-            for col in 0..cols {
-                if let Some(color) = self.get_color_at(row, col) {
-                    self.render_color_cell(row_layout[col], color, (row, col), buf);
+        for ((row, col), cell) in self.cell_rects(area) {
+            if let Some(mut color) = self.get_color_at(row, col) {
+                let picked = self.selection.contains(&color);
+                let label = self.show_labels.then(|| swatch_label(color)).flatten();
+                if !self.matches_search(color)
+                    && let Some(rgb) = approximate_rgb(color)
+                {
+                    let (r, g, b) = mix_rgb(rgb, (0, 0, 0), 0.8);
+                    color = Color::Rgb(r, g, b);
                 }
+
+                let focused = matches!(self.focus, Focus::Grid | Focus::Search);
+                let marker = match (self.grid_index == (row, col), focused) {
+                    (true, true) => Marker::Focused,
+                    (true, false) => Marker::Selected,
+                    (false, _) => Marker::None,
+                };
+                self.render_color_cell(cell, color, marker, picked, label, buf);
             }
         }
+
+        if let Some(scrollbar_area) = scrollbar_area {
+            let mut state = ScrollbarState::new(rows - visible + 1)
+                .position(self.grid_scroll.get())
+                .viewport_content_length(visible);
+            Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .render(scrollbar_area, buf, &mut state);
+        }
+    }
+
+    /// Smallest height of a grid row. Fixed-width swatches are half as tall as
+    /// they are wide, which looks roughly square in a terminal.
+    fn row_height(&self) -> u16 {
+        self.swatch_width
+            .map_or(GRID_ROW_HEIGHT, |width| (width / 2).max(GRID_ROW_HEIGHT))
+    }
+
+    /// Empty cells between neighbouring swatches
+    fn gap(&self) -> u16 {
+        u16::from(self.grid_gaps)
+    }
+
+    /// Number of grid rows that fit in `area` at their minimum height, counting
+    /// the gap below every row but the last
+    fn visible_rows(&self, area: Rect) -> usize {
+        let (rows, _) = self.grid_dimensions;
+        let fit = (area.height + self.gap()) / (self.row_height() + self.gap());
+        rows.min(usize::from(fit).max(1))
+    }
+
+    /// Scroll just enough to bring the selected row into view
+    fn scroll_to_selection(&self, visible: usize) {
+        let (rows, _) = self.grid_dimensions;
+        let row = self.grid_index.0;
+        let mut offset = self.grid_scroll.get().min(rows.saturating_sub(visible));
+
+        if row < offset {
+            offset = row;
+        } else if row >= offset + visible {
+            offset = row + 1 - visible;
+        }
+
+        self.grid_scroll.set(offset);
+    }
+
+    /// Rects of the visible grid cells inside `area` with their `(row, col)`,
+    /// in row-major order
+    fn cell_rects(&self, area: Rect) -> Vec<((usize, usize), Rect)> {
+        if self.compact_active.get() {
+            return self.compact_cell_rects(area);
+        }
+
+        let (_, cols) = self.grid_dimensions;
+        let visible = self.visible_rows(area);
+        let offset = self.grid_scroll.get();
+
+        // Fixed-width swatches keep their size and sit centered in the grid
+        let (row_layout, col_layout) = match self.swatch_width {
+            Some(width) => (
+                Layout::vertical(vec![Constraint::Length(self.row_height()); visible]),
+                Layout::horizontal(vec![Constraint::Length(width); cols]).flex(Flex::Center),
+            ),
+            None => (
+                Layout::vertical(vec![Constraint::Ratio(1, visible as u32); visible]),
+                Layout::horizontal(vec![Constraint::Ratio(1, cols as u32); cols]),
+            ),
+        };
+        let row_layout = row_layout.spacing(self.gap());
+        let col_layout = col_layout.spacing(self.gap());
+
+        row_layout
+            .split(area)
+            .iter()
+            .enumerate()
+            .flat_map(|(i, &row_area)| {
+                col_layout
+                    .split(row_area)
+                    .iter()
+                    .enumerate()
+                    .map(|(col, &cell)| ((offset + i, col), cell))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// The compact palette shows only the selected row, so Up and Down step
+    /// through the shades of every hue at once. Columns that don't fit scroll
+    /// to keep the selection near the middle.
+    fn compact_cell_rects(&self, area: Rect) -> Vec<((usize, usize), Rect)> {
+        let (_, cols) = self.grid_dimensions;
+        let (row, col) = self.grid_index;
+        let visible = cols.min(usize::from(area.width / COMPACT_SWATCH_WIDTH).max(1));
+        let offset = col.saturating_sub(visible / 2).min(cols - visible);
+
+        Layout::horizontal(vec![Constraint::Ratio(1, visible as u32); visible])
+            .spacing(self.gap())
+            .split(area)
+            .iter()
+            .enumerate()
+            .map(|(i, &cell)| ((row, offset + i), cell))
+            .collect()
+    }
+
+    /// Map a screen position to the grid cell under it, based on the last render
+    pub fn grid_position_at(&self, position: Position) -> Option<(usize, usize)> {
+        if self.show_sliders {
+            return None;
+        }
+
+        self.cell_rects(self.grid_area.get())
+            .into_iter()
+            .find(|(_, cell)| cell.contains(position))
+            .map(|(index, _)| index)
     }
 
     fn get_color_at(&self, row: usize, col: usize) -> Option<Color> {
         let (_, cols) = self.grid_dimensions;
         let idx = row * cols + col;
-        self.colors.get(idx).copied()
+        self.colors.get(idx).map(|&color| self.adjust(color))
     }
 
+    /// Fill `area` with `color`, then draw the selection marker, a check in the
+    /// top-right corner when it's `picked` for the selection and, when the
+    /// cell is wide enough, `label` centered on top
     fn render_color_cell(
         &self,
         area: Rect,
         color: Color,
-        position: (usize, usize),
+        marker: Marker,
+        picked: bool,
+        label: Option<String>,
         buf: &mut Buffer,
     ) {
+        let color = self.vision.simulate(color);
         buf.set_style(area, Style::default().bg(color).fg(color));
 
-        if self.grid_index == position {
-            let selection_block = Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::White));
-            selection_block.render(area, buf);
+        // Pick the marker color from the swatch so it stays visible on light shades
+        let border_style = Style::default().fg(contrasting_color(color));
+        let selection_block = match marker {
+            Marker::None => None,
+            Marker::Selected => Some(Block::default().border_style(border_style)),
+            Marker::Focused => Some(
+                Block::default()
+                    .border_type(BorderType::Thick)
+                    .border_style(border_style.add_modifier(Modifier::BOLD)),
+            ),
+        };
+        if let Some(block) = selection_block {
+            block.borders(Borders::ALL).render(area, buf);
+        }
+
+        if picked
+            && !area.is_empty()
+            && let Some(cell) = buf.cell_mut((area.right() - 1, area.y))
+        {
+            cell.set_char('✓').set_style(border_style);
+        }
+
+        if let Some(label) = label
+            && area.width >= 7
+            && area.height > 0
+        {
+            let row = Rect {
+                y: area.y + (area.height - 1) / 2,
+                height: 1,
+                ..area
+            };
+            Line::styled(label, border_style)
+                .centered()
+                .render(row, buf);
         }
     }
 
@@ -243,96 +1551,706 @@ impl ColorPickerWidget {
         let apply_focused = self.focus == Focus::Apply;
         let cancel_focused = self.focus == Focus::Cancel;
 
-        Button::new("Apply")
+        let hint = |key| Line::styled(format!("[{key}]"), self.theme.muted());
+        // Two ticks per shade, a slow enough pulse to catch the eye without nagging
+        let pulse = self.animations && self.animation_phase / 2 % 2 == 1;
+
+        Button::new(vec![Line::raw("Apply"), hint(self.keymap.confirm)])
+            .icon("✓")
             .state(if apply_focused {
                 State::Selected
             } else {
                 State::Normal
             })
             .focused(apply_focused)
+            .theme(self.theme)
+            .pulse(pulse)
             .render(buttons[0], buf);
 
-        Button::new("Cancel")
+        Button::new(vec![Line::raw("Cancel"), hint(Key(KeyCode::Esc))])
+            .icon("✗")
             .state(if cancel_focused {
                 State::Selected
             } else {
                 State::Normal
             })
             .focused(cancel_focused)
+            .theme(self.theme)
+            .pulse(pulse)
             .render(buttons[2], buf);
     }
 
     fn render_text_inputs(&self, area: Rect, buf: &mut Buffer) {
-        let border_color = Styles::border_color(
-            self.focus == Focus::Input,
-            Some(self.color_input.is_valid()),
-        );
+        let [area, preview_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(10)]).areas(area);
+        self.render_color_preview(preview_area, buf);
+
+        let validity = self.color_input.validity();
+        let border_color = if self.input_rejected() {
+            self.theme.invalid
+        } else {
+            self.theme
+                .border_color(self.focus == Focus::Input, validity)
+        };
 
-        let input_block = Block::default()
+        let mut input_block = Block::default()
             .borders(Borders::ALL)
-            .title("HEX Color")
+            .title(format!("{} Color", self.color_input.mode.label()))
             .border_style(Style::default().fg(border_color));
 
+        if let Some(color) = self.color_input.color() {
+            let name = Line::from(format!("≈ {}", nearest_named(color)));
+            input_block = input_block.title(name.right_aligned());
+        }
+        if let Some((typed, needed)) = self.color_input.hex_progress() {
+            let counter = Line::from(format!("{typed}/{needed}"));
+            input_block = input_block.title_bottom(counter.right_aligned());
+        }
+
         input_block.render(area, buf);
 
         let input_area = Rect {
             x: area.x + 1,
             y: area.y + 1,
-            width: area.width - 2,
+            width: area.width.saturating_sub(2),
             height: 1,
         };
 
         ColorInputWidget {
             input: &self.color_input,
             focused: self.focus == Focus::Input,
+            cursor_visible: self.cursor_visible(),
+            background: self.theme.background,
+            invalid: self.theme.invalid,
         }
         .render(input_area, buf);
     }
+
+    /// Read-only RGB and HSL decomposition of the current color
+    fn render_breakdown(&self, area: Rect, buf: &mut Buffer) {
+        let text = match self.color_input.color().and_then(rgb_components) {
+            Some((r, g, b)) => {
+                let (h, s, l) = rgb_to_hsl(r, g, b);
+                format!("RGB({r},{g},{b}) HSL({h},{s},{l})")
+            }
+            None => "RGB(--) HSL(--)".to_string(),
+        };
+
+        // Lines clip to `area`, so a squeezed layout can't write past it
+        Line::raw(text).render(area, buf);
+    }
+
+    /// The focused section and its most useful keys, kept below the buttons
+    fn render_hints(&self, area: Rect, buf: &mut Buffer) {
+        Line::styled(self.focus_hint(), self.theme.muted()).render(area, buf);
+    }
+
+    /// Approximate print inks for the current color
+    fn render_cmyk(&self, area: Rect, buf: &mut Buffer) {
+        let text = match self.color_input.color().and_then(rgb_components) {
+            Some((r, g, b)) => {
+                let (c, m, y, k) = rgb_to_cmyk(r, g, b);
+                format!("CMYK({c}%,{m}%,{y}%,{k}%)")
+            }
+            None => "CMYK(--)".to_string(),
+        };
+
+        Line::raw(text).render(area, buf);
+    }
+
+    /// The SGR escape sequence for the current color, foreground or background
+    fn render_escape(&self, area: Rect, buf: &mut Buffer) {
+        let layer = if self.escape_background { "BG" } else { "FG" };
+        let sequence = self.escape_sequence().unwrap_or_else(|| "--".to_string());
+
+        Line::raw(format!("{layer} {sequence}")).render(area, buf);
+    }
+
+    /// Escape sequence for the current color on the chosen layer, as copyable text
+    pub fn escape_sequence(&self) -> Option<String> {
+        ansi_escape(self.current_color()?, self.escape_background)
+    }
+
+    /// WCAG contrast of the current color against black and white text
+    fn render_contrast(&self, area: Rect, buf: &mut Buffer) {
+        let text = match self.color_input.color() {
+            Some(Color::Rgb(r, g, b)) => {
+                let luminance = relative_luminance(r, g, b);
+                let describe = |label: &str, other: f64| {
+                    let ratio = contrast_ratio(luminance, other);
+                    let mark = |min: f64| if ratio >= min { '✓' } else { '✗' };
+                    format!(
+                        "{label} {ratio:.1} AA{} AAA{}",
+                        mark(AA_RATIO),
+                        mark(AAA_RATIO)
+                    )
+                };
+
+                format!("{} │ {}", describe("Black", 0.0), describe("White", 1.0))
+            }
+            _ => "Contrast: --".to_string(),
+        };
+
+        Line::raw(text).render(area, buf);
+    }
+
+    /// The original color beside the edited one, with how far each channel
+    /// moved and the perceptual difference between them
+    fn render_comparison(&self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default().borders(Borders::ALL).title("Compare");
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let rgbs = self
+            .original
+            .and_then(approximate_rgb)
+            .zip(self.current_color().and_then(approximate_rgb));
+        let Some((from, to)) = rgbs else {
+            Line::raw("No color to compare").render(inner, buf);
+            return;
+        };
+
+        let [swatches, deltas] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(inner);
+        let swatch = |(r, g, b)| {
+            let shown = self.vision.simulate(Color::Rgb(r, g, b));
+            Span::styled("    ", Style::default().bg(shown))
+        };
+        let difference = delta_e(from, to);
+        let verdict = if difference < JUST_NOTICEABLE {
+            " (not visible)"
+        } else {
+            ""
+        };
+        Line::from(vec![
+            swatch(from),
+            Span::raw(format!(" {} → ", rgb_to_hex(from.0, from.1, from.2))),
+            swatch(to),
+            Span::raw(format!(" {}", rgb_to_hex(to.0, to.1, to.2))),
+            Span::raw(format!("  ΔE {difference:.1}{verdict}")),
+        ])
+        .render(swatches, buf);
+
+        let (h1, s1, l1) = rgb_to_hsl(from.0, from.1, from.2);
+        let (h2, s2, l2) = rgb_to_hsl(to.0, to.1, to.2);
+        let channel = |a: u8, b: u8| i16::from(b) - i16::from(a);
+        // The shorter way round the wheel, so 350° to 10° is +20, not -340
+        let hue = (h2 as i16 - h1 as i16 + 540) % 360 - 180;
+        Line::raw(format!(
+            "ΔRGB({:+},{:+},{:+}) ΔHSL({hue:+},{:+},{:+})",
+            channel(from.0, to.0),
+            channel(from.1, to.1),
+            channel(from.2, to.2),
+            channel(s1, s2),
+            channel(l1, l2),
+        ))
+        .render(deltas, buf);
+    }
+
+    /// A wide block filled with the current color, its hex code on top
+    fn render_banner(&self, area: Rect, buf: &mut Buffer) {
+        let input = &self.color_input;
+        let color = input.color().or_else(|| self.base_color());
+        let alpha = input.color().and(input.alpha());
+
+        let (label, text_color) = match color {
+            Some(color) => {
+                let shown = self.vision.simulate(color);
+                match alpha {
+                    Some(alpha) => {
+                        render_checkerboard(area, Some((shown, alpha)), &self.theme, buf)
+                    }
+                    None => buf.set_style(area, Style::default().bg(shown)),
+                }
+                let label = swatch_label(color).unwrap_or_default();
+                (label, contrasting_color(shown))
+            }
+            None => {
+                render_checkerboard(area, None, &self.theme, buf);
+                ("No color".to_string(), Color::Black)
+            }
+        };
+
+        let [row] = Layout::vertical([Constraint::Length(1)])
+            .flex(Flex::Center)
+            .areas(area);
+        Line::styled(
+            label,
+            Style::default().fg(text_color).add_modifier(Modifier::BOLD),
+        )
+        .centered()
+        .render(row, buf);
+    }
+
+    /// Swatch showing the color currently parsed from the input
+    fn render_color_preview(&self, area: Rect, buf: &mut Buffer) {
+        let preview_block = Block::default().borders(Borders::ALL).title("Preview");
+        let inner = preview_block.inner(area);
+        preview_block.render(area, buf);
+
+        // Show alpha against a checkerboard; with no valid color, show just the board
+        match (self.color_input.color(), self.color_input.alpha()) {
+            (Some(color), None) => buf.set_style(inner, Style::default().bg(color)),
+            (Some(color), Some(alpha)) => {
+                render_checkerboard(inner, Some((color, alpha)), &self.theme, buf)
+            }
+            (None, _) => render_checkerboard(inner, None, &self.theme, buf),
+        }
+    }
+}
+
+/// Text drawn on a labelled swatch: its hex code, or the index of an ANSI color
+fn swatch_label(color: Color) -> Option<String> {
+    ColorPickerWidget::color_to_hex(color)
+        .map(|hex| format!("#{hex}"))
+        .or_else(|| ColorPickerWidget::color_to_ansi_index(color).map(|i| i.to_string()))
+}
+
+/// Fill `area` with a two-tone checkerboard, optionally composited under a
+/// color with the given alpha. Squares are two cells wide so they look square.
+fn render_checkerboard(area: Rect, over: Option<(Color, u8)>, theme: &Theme, buf: &mut Buffer) {
+    let (light, dark) = (theme.checker_light, theme.checker_dark);
+    let over = over.and_then(|(color, alpha)| Some((rgb_components(color)?, alpha)));
+
+    for position in area.positions() {
+        let checker = if (position.x / 2 + position.y) % 2 == 0 {
+            light
+        } else {
+            dark
+        };
+        let (r, g, b) = match over {
+            Some((rgb, alpha)) => mix_rgb(checker, rgb, f64::from(alpha) / 255.0),
+            None => checker,
+        };
+
+        if let Some(cell) = buf.cell_mut(position) {
+            cell.set_bg(Color::Rgb(r, g, b));
+        }
+    }
+}
+
+fn render_confirm_discard(modal_area: Rect, theme: &Theme, buf: &mut Buffer) {
+    let [area] = Layout::horizontal([Constraint::Length(28)])
+        .flex(Flex::Center)
+        .areas(modal_area);
+    let [area] = Layout::vertical([Constraint::Length(3)])
+        .flex(Flex::Center)
+        .areas(area);
+
+    Clear.render(area, buf);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(theme.focus_border(true))
+        .style(theme.modal_background());
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    Line::raw("Discard changes? (y/n)")
+        .centered()
+        .render(inner, buf);
 }
 
-fn create_modal_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
-    let popup_width = (area.width * percent_x) / 100;
-    let popup_height = (area.height * percent_y) / 100;
+/// A small box over the bottom edge of the modal
+fn render_toast(modal_area: Rect, message: &str, theme: &Theme, buf: &mut Buffer) {
+    let width = (message.chars().count() as u16 + 4).min(modal_area.width);
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(modal_area);
+    let [area] = Layout::vertical([Constraint::Length(3)])
+        .flex(Flex::End)
+        .areas(area);
+
+    Clear.render(area, buf);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(theme.modal_background());
+    let inner = block.inner(area);
+    block.render(area, buf);
+
+    Line::raw(message).centered().render(inner, buf);
+}
+
+fn render_too_small(area: Rect, buf: &mut Buffer) {
+    Clear.render(area, buf);
+
+    let [line] = Layout::vertical([Constraint::Length(1)])
+        .flex(Flex::Center)
+        .areas(area);
+    Line::raw("Terminal too small").centered().render(line, buf);
+}
+
+/// The modal centered in `area` then shifted by `offset`, kept fully inside
+/// `area`. Also returns the offset left after clamping.
+fn create_modal_area(
+    area: Rect,
+    percent_x: u16,
+    percent_y: u16,
+    (dx, dy): (i16, i16),
+) -> (Rect, (i16, i16)) {
+    let scale = |length: u16, percent: u16, min: u16| {
+        let scaled = u32::from(length) * u32::from(percent.min(100)) / 100;
+        (scaled as u16).max(min).min(length)
+    };
+
+    let popup_width = scale(area.width, percent_x, MODAL_MIN_SIZE.0);
+    let popup_height = scale(area.height, percent_y, MODAL_MIN_SIZE.1);
     let vertical_margin = (area.height - popup_height) / 2;
     let horizontal_margin = (area.width - popup_width) / 2;
 
-    Rect::new(
-        area.x + horizontal_margin,
-        area.y + vertical_margin,
-        popup_width,
-        popup_height,
-    )
+    let shift = |margin: u16, free: u16, delta: i16| {
+        let shifted = margin.saturating_add_signed(delta).min(free);
+        (shifted, shifted as i16 - margin as i16)
+    };
+    let (x, dx) = shift(horizontal_margin, area.width - popup_width, dx);
+    let (y, dy) = shift(vertical_margin, area.height - popup_height, dy);
+
+    let modal = Rect::new(area.x + x, area.y + y, popup_width, popup_height);
+    (modal, (dx, dy))
 }
 
 pub struct ColorInputWidget<'a> {
     pub input: &'a ColorInput,
     pub focused: bool,
+    /// Whether the cursor is drawn this frame; toggled to make it blink
+    pub cursor_visible: bool,
+    /// Color behind the text, which is drawn in black or white to contrast with it
+    pub background: Color,
+    /// Color of characters the input mode can't use
+    pub invalid: Color,
 }
 
 impl Widget for ColorInputWidget<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let input_display = if self.input.input.is_empty() {
-            "#______".to_string()
+        let mode = self.input.mode;
+        let scroll = self.scroll(area.width);
+        let text = Style::default().fg(contrasting_color(self.background));
+        let line = if self.input.input.is_empty() {
+            // Italic tells the placeholder apart from typed text at the same contrast
+            Line::styled(mode.placeholder(), text.add_modifier(Modifier::ITALIC))
         } else {
-            self.input.input.clone()
+            // Flag characters the mode can't use so it's clear why the input is invalid
+            let invalid = Style::default().fg(self.invalid);
+            self.input
+                .input
+                .chars()
+                .skip(scroll)
+                .map(|c| {
+                    let style = if mode.accepts(c) { text } else { invalid };
+                    Span::styled(c.to_string(), style)
+                })
+                .collect()
         };
 
-        buf.set_string(area.x, area.y, &input_display, Style::default());
+        line.render(area, buf);
 
-        if self.focused {
+        if self.focused && self.cursor_visible {
             self.render_cursor(area, buf);
         }
     }
 }
 
 impl ColorInputWidget<'_> {
+    /// Characters hidden off the left edge so the cursor stays inside `width`,
+    /// leaving room for it after the last character
+    fn scroll(&self, width: u16) -> usize {
+        self.input
+            .cursor_pos
+            .saturating_sub(usize::from(width.saturating_sub(1)))
+    }
+
     fn render_cursor(&self, area: Rect, buf: &mut Buffer) {
-        let cursor_x = area.x + self.input.cursor_pos as u16;
+        let offset = self.input.cursor_pos - self.scroll(area.width);
+        let cursor_x = area.x.saturating_add(offset as u16);
         let cursor_y = area.y;
+        if !area.contains(Position::new(cursor_x, cursor_y)) {
+            return;
+        }
 
         if let Some(cell) = Buffer::cell_mut(buf, Position::new(cursor_x, cursor_y)) {
             cell.set_char('|');
-            cell.set_style(Style::default().add_modifier(ratatui::style::Modifier::RAPID_BLINK));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::crossterm::event::KeyEvent;
+
+    use super::*;
+    use crate::color_input::InputMode;
+
+    /// Every focus Tab visits from `start`, until it comes back around
+    fn tab_cycle(picker: &mut ColorPickerWidget, step: fn(&mut ColorPickerWidget)) -> Vec<Focus> {
+        let start = picker.focus;
+        let mut visited = vec![start];
+        loop {
+            step(picker);
+            if picker.focus == start {
+                return visited;
+            }
+            assert!(visited.len() < 16, "Tab never came back to {start:?}");
+            visited.push(picker.focus);
+        }
+    }
+
+    #[test]
+    fn tab_skips_empty_strips_and_wraps() {
+        let mut picker = ColorPickerWidget::default();
+        let order = [
+            Focus::Grid,
+            Focus::Shades,
+            Focus::Input,
+            Focus::Apply,
+            Focus::Cancel,
+        ];
+
+        assert_eq!(picker.focus_order(), order);
+        assert_eq!(tab_cycle(&mut picker, ColorPickerWidget::focus_next), order);
+
+        // From the last section Tab wraps to the first, and back again
+        picker.focus = Focus::Cancel;
+        picker.focus_next();
+        assert_eq!(picker.focus, Focus::Grid);
+        picker.focus_prev();
+        assert_eq!(picker.focus, Focus::Cancel);
+    }
+
+    #[test]
+    fn shift_tab_visits_the_same_sections_backwards() {
+        let mut picker = ColorPickerWidget {
+            show_hsl: true,
+            recent: vec![Color::Rgb(1, 2, 3)],
+            favorites: vec![Color::Rgb(4, 5, 6)],
+            ..Default::default()
+        };
+        let order = [
+            Focus::Grid,
+            Focus::Hsl,
+            Focus::Shades,
+            Focus::Recent,
+            Focus::Favorites,
+            Focus::Input,
+            Focus::Apply,
+            Focus::Cancel,
+        ];
+
+        assert_eq!(picker.focus_order(), order);
+        assert_eq!(tab_cycle(&mut picker, ColorPickerWidget::focus_next), order);
+
+        let mut backwards = order;
+        backwards[1..].reverse();
+        assert_eq!(
+            tab_cycle(&mut picker, ColorPickerWidget::focus_prev),
+            backwards
+        );
+    }
+
+    #[test]
+    fn tab_from_outside_the_cycle_lands_on_an_end() {
+        let mut picker = ColorPickerWidget {
+            focus: Focus::Search,
+            ..Default::default()
+        };
+
+        picker.focus_next();
+        assert_eq!(picker.focus, Focus::Grid);
+
+        picker.focus = Focus::Search;
+        picker.focus_prev();
+        assert_eq!(picker.focus, Focus::Cancel);
+    }
+
+    #[test]
+    fn tab_follows_the_sliders_in_place_of_the_grid() {
+        let mut picker = ColorPickerWidget {
+            show_sliders: true,
+            focus: Focus::Cancel,
+            ..Default::default()
+        };
+
+        picker.focus_next();
+        assert_eq!(picker.focus, Focus::Sliders);
+        assert!(!picker.focus_order().contains(&Focus::Grid));
+    }
+
+    #[test]
+    fn selection_marker_contrasts_with_the_swatch() {
+        let picker = ColorPickerWidget::default();
+        let area = Rect::new(0, 0, 6, 3);
+
+        for (swatch, marker_color) in [
+            (Color::Rgb(0xFA, 0xFA, 0xFA), Color::Black),
+            (Color::Rgb(0xFF, 0xF9, 0xC4), Color::Black),
+            (Color::Rgb(0x12, 0x12, 0x12), Color::White),
+            (Color::Rgb(0x1A, 0x23, 0x7E), Color::White),
+        ] {
+            for marker in [Marker::Selected, Marker::Focused] {
+                let mut buf = Buffer::empty(area);
+                picker.render_color_cell(area, swatch, marker, true, None, &mut buf);
+
+                // The border corner and the picked check mark
+                for position in [(0, 0), (5, 0)] {
+                    assert_eq!(buf[position].fg, marker_color, "{swatch:?} {marker:?}");
+                    assert_eq!(buf[position].bg, swatch);
+                }
+            }
+        }
+    }
+
+    /// Render `picker` into every buffer up to 70x50, which must not panic
+    fn render_at_every_size(picker: &ColorPickerWidget) {
+        for width in 0..70 {
+            for height in 0..50 {
+                let mut buf = Buffer::empty(Rect::new(0, 0, width, height));
+                picker.render(buf.area, &mut buf);
+            }
+        }
+    }
+
+    /// A picker with every optional row shown at once
+    fn busy_picker() -> ColorPickerWidget {
+        ColorPickerWidget {
+            modal_state: true,
+            show_hsl: true,
+            recent: vec![Color::Rgb(1, 2, 3)],
+            favorites: vec![Color::Rgb(4, 5, 6)],
+            selection: vec![Color::Rgb(7, 8, 9)],
+            original: Some(Color::Rgb(10, 11, 12)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plain_picker_renders_at_any_size() {
+        render_at_every_size(&ColorPickerWidget {
+            modal_state: true,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn busy_picker_renders_at_any_size() {
+        render_at_every_size(&busy_picker());
+    }
+
+    #[test]
+    fn sliders_render_at_any_size() {
+        render_at_every_size(&ColorPickerWidget {
+            show_sliders: true,
+            ..busy_picker()
+        });
+    }
+
+    #[test]
+    fn compact_picker_renders_at_any_size() {
+        render_at_every_size(&ColorPickerWidget {
+            compact: true,
+            ..busy_picker()
+        });
+    }
+
+    #[test]
+    fn checkerboard_follows_the_theme() {
+        for theme in [Theme::dark(), Theme::light()] {
+            let picker = ColorPickerWidget {
+                theme,
+                ..Default::default()
+            };
+            // An empty input shows the bare board in the preview
+            let area = Rect::new(0, 0, 6, 4);
+            let mut buf = Buffer::empty(area);
+            picker.render_color_preview(area, &mut buf);
+
+            let rgb = |(r, g, b)| Color::Rgb(r, g, b);
+            // Squares are two cells wide
+            assert_eq!(buf[(1, 1)].bg, rgb(theme.checker_dark));
+            assert_eq!(buf[(2, 1)].bg, rgb(theme.checker_light));
+            assert_eq!(buf[(3, 1)].bg, rgb(theme.checker_light));
+            assert_eq!(buf[(1, 2)].bg, rgb(theme.checker_light));
+        }
+    }
+
+    #[test]
+    fn search_finds_indexed_swatches_by_name() {
+        let mut picker = ColorPickerWidget::default();
+        picker.set_builtin_palette(Palette::Ansi16);
+        picker.search = "red".to_string();
+
+        assert!(picker.matches_search(Color::Indexed(1)));
+        assert!(picker.matches_search(Color::Indexed(9)));
+        assert!(!picker.matches_search(Color::Indexed(4)));
+
+        assert!(picker.snap_to_search());
+        assert_eq!(picker.selected_color(), Some(Color::Indexed(1)));
+    }
+
+    /// An input in `mode` with `text` typed into it, as far as it fits
+    fn typed(mode: InputMode, text: &str) -> ColorInput {
+        let mut input = ColorInput::default();
+        input.mode = mode;
+        for c in text.chars() {
+            input.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        input
+    }
+
+    fn input_widget(input: &ColorInput) -> ColorInputWidget<'_> {
+        ColorInputWidget {
+            input,
+            focused: true,
+            cursor_visible: true,
+            background: Color::Black,
+            invalid: Color::Red,
+        }
+    }
+
+    fn row_text(buf: &Buffer) -> String {
+        buf.content.iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn full_input_renders_at_any_width() {
+        let full = [
+            (InputMode::Hex, "FFFFFFFFFFFF"),
+            (InputMode::Hex, "lightgoldenrodyellow!"),
+            (InputMode::Rgb, "255, 255, 255, 255"),
+            (InputMode::Hsl, "360,100,100,100"),
+            (InputMode::Cmyk, "100,100,100,100,100"),
+        ];
+
+        for (mode, text) in full {
+            let input = typed(mode, text);
+            assert_eq!(input.cursor_pos, input.input.len(), "{text:?}");
+
+            for width in 0..25 {
+                let mut buf = Buffer::empty(Rect::new(0, 0, width, 1));
+                input_widget(&input).render(buf.area, &mut buf);
+            }
+        }
+    }
+
+    #[test]
+    fn input_scrolls_to_keep_the_cursor_in_view() {
+        let name = typed(InputMode::Hex, "lightgoldenrodyellow");
+        assert_eq!(name.input.len(), 20);
+
+        // Wide enough: nothing scrolls and the cursor follows the text
+        let mut buf = Buffer::empty(Rect::new(0, 0, 24, 1));
+        input_widget(&name).render(buf.area, &mut buf);
+        assert_eq!(row_text(&buf), "lightgoldenrodyellow|   ");
+
+        // Too narrow: the start scrolls off and the cursor takes the last cell
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 1));
+        input_widget(&name).render(buf.area, &mut buf);
+        assert_eq!(row_text(&buf), "dyellow|");
+
+        // Back at the start, the view scrolls home again
+        let mut home = name.clone();
+        home.handle_key_event(KeyEvent::from(KeyCode::Home));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 8, 1));
+        input_widget(&home).render(buf.area, &mut buf);
+        assert_eq!(row_text(&buf), "|ightgol");
+    }
+}
@@ -0,0 +1,195 @@
+use std::str::FromStr;
+
+use color_eyre::eyre::{Report, eyre};
+use ratatui::style::Color;
+use serde::Serialize;
+
+use crate::{
+    modal::ColorPickerWidget,
+    util::{color_support::approximate_rgb, convert::rgb_to_hsl},
+};
+
+/// How the applied color is written out
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// `#RRGGBB`, or `#RRGGBBAA` with alpha
+    #[default]
+    Hex,
+    /// `rgb(255, 136, 0)`, or `rgba(...)` with alpha
+    Rgb,
+    /// `hsl(32, 100%, 50%)`, or `hsla(...)` with alpha
+    Hsl,
+    /// Foreground SGR escape, `\e[38;2;255;136;0m` or `\e[38;5;208m` for indexed colors
+    Ansi,
+    /// `{"hex":"#FF8800","rgb":[255,136,0],"hsl":[32,100,50],"colorspace":"srgb"}`
+    /// for scripts, or `{"ansi":208,"colorspace":"ansi"}` for indexed colors,
+    /// whose RGB depends on the terminal's palette
+    Json,
+    /// A CSS declaration, `color: #FF8800;`, or a custom property like
+    /// `--brand: #FF8800;` when given a name
+    Css,
+}
+
+/// JSON shape of an RGB color
+#[derive(Serialize)]
+struct JsonColor {
+    hex: String,
+    rgb: [u8; 3],
+    hsl: (u16, u8, u8),
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alpha: Option<u8>,
+    /// Always `srgb`, so color-critical tools don't have to guess
+    colorspace: &'static str,
+}
+
+/// Color space of the terminal the output is meant for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Gamut {
+    #[default]
+    Srgb,
+    /// Display P3. The picker can't convert to it yet, so output stays sRGB
+    /// and looks less saturated than intended on these terminals.
+    P3,
+}
+
+impl OutputFormat {
+    /// Indexed colors come out as their palette index, e.g. `208`, except in
+    /// the ANSI format, and as their usual RGB in CSS. ANSI output ignores alpha.
+    pub fn format(self, color: Color, alpha: Option<u8>) -> Option<String> {
+        self.format_named(color, alpha, None)
+    }
+
+    /// Like [`Self::format`], but CSS output declares the custom property
+    /// `--{css_name}` rather than `color`
+    pub fn format_named(
+        self,
+        color: Color,
+        alpha: Option<u8>,
+        css_name: Option<&str>,
+    ) -> Option<String> {
+        if self == Self::Css {
+            let (r, g, b) = approximate_rgb(color)?;
+            let hex = Self::Hex.format(Color::Rgb(r, g, b), alpha)?;
+            let property = css_name.map_or("color".to_string(), |name| format!("--{name}"));
+            return Some(format!("{property}: {hex};"));
+        }
+
+        if let Some(index) = ColorPickerWidget::color_to_ansi_index(color) {
+            return Some(match self {
+                Self::Ansi => ansi_escape(color, false)?,
+                Self::Json => {
+                    serde_json::json!({ "ansi": index, "colorspace": "ansi" }).to_string()
+                }
+                _ => index.to_string(),
+            });
+        }
+
+        let Color::Rgb(r, g, b) = color else {
+            return None;
+        };
+        let fraction = |a: u8| f64::from(a) / 255.0;
+
+        let output = match (self, alpha) {
+            (Self::Hex, None) => format!("#{}", ColorPickerWidget::color_to_hex(color)?),
+            (Self::Hex, Some(a)) => format!("#{}", ColorPickerWidget::color_to_hexa(color, a)?),
+            (Self::Rgb, None) => format!("rgb({r}, {g}, {b})"),
+            (Self::Rgb, Some(a)) => format!("rgba({r}, {g}, {b}, {:.2})", fraction(a)),
+            (Self::Ansi, _) => ansi_escape(color, false)?,
+            (Self::Css, _) => unreachable!("CSS is formatted above"),
+            (Self::Json, _) => {
+                let json = JsonColor {
+                    hex: format!("#{}", ColorPickerWidget::color_to_hex(color)?),
+                    rgb: [r, g, b],
+                    hsl: rgb_to_hsl(r, g, b),
+                    alpha,
+                    colorspace: "srgb",
+                };
+                serde_json::to_string(&json).ok()?
+            }
+            (Self::Hsl, _) => {
+                let (h, s, l) = rgb_to_hsl(r, g, b);
+                match alpha {
+                    None => format!("hsl({h}, {s}%, {l}%)"),
+                    Some(a) => format!("hsla({h}, {s}%, {l}%, {:.2})", fraction(a)),
+                }
+            }
+        };
+
+        Some(output)
+    }
+
+    /// Several colors at once: a JSON array, or one color per line otherwise.
+    /// Named CSS properties are numbered, `--brand-1`, `--brand-2` and so on.
+    /// Colors that can't be formatted are left out.
+    pub fn format_list(self, colors: &[Color], css_name: Option<&str>) -> String {
+        let formatted = colors.iter().enumerate().filter_map(|(i, &color)| {
+            let name = css_name.map(|name| format!("{name}-{}", i + 1));
+            self.format_named(color, None, name.as_deref())
+        });
+
+        match self {
+            Self::Json => format!("[{}]", formatted.collect::<Vec<_>>().join(",")),
+            _ => formatted.collect::<Vec<_>>().join("\n"),
+        }
+    }
+}
+
+/// The SGR escape that sets `color` as the foreground, or the background, written
+/// out as copyable text: `\e[38;2;255;136;0m` for RGB, `\e[48;5;208m` for an
+/// indexed background. `None` for named colors and `Reset`.
+pub fn ansi_escape(color: Color, background: bool) -> Option<String> {
+    let layer = if background { 48 } else { 38 };
+    match color {
+        Color::Rgb(r, g, b) => Some(format!("\\e[{layer};2;{r};{g};{b}m")),
+        Color::Indexed(index) => Some(format!("\\e[{layer};5;{index}m")),
+        _ => None,
+    }
+}
+
+impl FromStr for Gamut {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "srgb" => Ok(Self::Srgb),
+            "p3" | "display-p3" => Ok(Self::P3),
+            _ => Err(eyre!("unknown gamut `{s}`, expected one of: srgb, p3")),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hex" => Ok(Self::Hex),
+            "rgb" => Ok(Self::Rgb),
+            "hsl" => Ok(Self::Hsl),
+            "ansi" => Ok(Self::Ansi),
+            "json" => Ok(Self::Json),
+            "css" => Ok(Self::Css),
+            _ => Err(eyre!(
+                "unknown format `{s}`, expected one of: hex, rgb, hsl, ansi, json, css"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_names_the_colorspace() {
+        let json = |color| {
+            let output = OutputFormat::Json.format(color, None).unwrap();
+            serde_json::from_str::<serde_json::Value>(&output).unwrap()
+        };
+
+        assert_eq!(json(Color::Rgb(255, 136, 0))["colorspace"], "srgb");
+        let indexed = json(Color::Indexed(208));
+        assert_eq!(indexed["ansi"], 208);
+        assert_eq!(indexed["colorspace"], "ansi");
+    }
+}
@@ -0,0 +1,395 @@
+use std::{cmp::Reverse, fs, path::Path};
+
+use color_eyre::Result;
+use image::ImageReader;
+use ratatui::style::{
+    Color,
+    palette::{material, tailwind},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::util::{
+    convert::{hsl_to_rgb, rgb_to_hsl},
+    parse::parse_color,
+};
+
+/// TOML palette file layout: `colors = ["#FF8800", ...]`
+#[derive(Debug, Deserialize)]
+struct PaletteFile {
+    colors: Vec<String>,
+}
+
+/// Load a palette from a TOML file (`.toml`) or a plain list with one hex color per line.
+/// Entries that aren't valid hex colors are skipped with a warning on stderr.
+pub fn load_palette(path: &Path) -> Result<Vec<Color>> {
+    let contents = fs::read_to_string(path)?;
+
+    let entries: Vec<String> = if path.extension().is_some_and(|ext| ext == "toml") {
+        toml::from_str::<PaletteFile>(&contents)?.colors
+    } else {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    let colors = entries
+        .iter()
+        .filter_map(|entry| {
+            let color = parse_color(entry).map(|(color, _)| color);
+            if color.is_none() {
+                eprintln!("Skipping invalid palette entry `{entry}`");
+            }
+            color
+        })
+        .collect();
+
+    Ok(colors)
+}
+
+/// Longest side an image is shrunk to before clustering. Plenty to find the
+/// dominant colors, and it keeps huge photos quick.
+const IMAGE_SAMPLE_SIZE: u32 = 128;
+
+/// Extract up to `count` dominant colors from an image with median cut, most
+/// common first, each with the share of pixels it stands for. The format is
+/// guessed from the file contents.
+pub fn load_image_palette(path: &Path, count: usize) -> Result<Vec<(Color, f64)>> {
+    let image = ImageReader::open(path)?.with_guessed_format()?.decode()?;
+    let pixels = image
+        .thumbnail(IMAGE_SAMPLE_SIZE, IMAGE_SAMPLE_SIZE)
+        .to_rgb8()
+        .pixels()
+        .map(|pixel| pixel.0)
+        .collect();
+
+    Ok(median_cut(pixels, count))
+}
+
+/// Split the pixels into up to `count` boxes, each time halving the box with the
+/// widest channel range at its median, then average each box. Each color comes
+/// with the fraction of the pixels in its box.
+fn median_cut(pixels: Vec<[u8; 3]>, count: usize) -> Vec<(Color, f64)> {
+    let total = pixels.len().max(1) as f64;
+    let mut boxes = vec![pixels];
+    boxes.retain(|pixels| !pixels.is_empty());
+
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, pixels)| (i, widest_channel(pixels)))
+            .max_by_key(|&(_, (_, range))| range);
+
+        // Every box is a single color by now
+        let Some((index, (channel, range))) = widest else {
+            break;
+        };
+        if range == 0 {
+            break;
+        }
+
+        let mut lower = boxes.swap_remove(index);
+        lower.sort_unstable_by_key(|pixel| pixel[channel]);
+        let upper = lower.split_off(lower.len() / 2);
+        boxes.extend([lower, upper]);
+    }
+
+    boxes.sort_by_key(|pixels| Reverse(pixels.len()));
+
+    // Halves of a large flat area average to the same color, so merge their shares
+    let mut colors: Vec<(Color, f64)> = Vec::with_capacity(boxes.len());
+    for pixels in &boxes {
+        let color = average(pixels);
+        let share = pixels.len() as f64 / total;
+        match colors.iter_mut().find(|(c, _)| *c == color) {
+            Some((_, weight)) => *weight += share,
+            None => colors.push((color, share)),
+        }
+    }
+    colors
+}
+
+/// The channel whose values are most spread out, and that spread
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = pixels.iter().map(|pixel| pixel[channel]);
+            let min = values.clone().min().unwrap_or_default();
+            let max = values.max().unwrap_or_default();
+            (channel, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or_default()
+}
+
+fn average(pixels: &[[u8; 3]]) -> Color {
+    let len = pixels.len().max(1) as u64;
+    let mean = |channel: usize| {
+        let sum: u64 = pixels.iter().map(|pixel| u64::from(pixel[channel])).sum();
+        (sum / len) as u8
+    };
+    Color::Rgb(mean(0), mean(1), mean(2))
+}
+
+/// Pick a rows x cols layout for `count` swatches, favoring more columns because
+/// terminal cells are taller than they are wide
+pub fn grid_dimensions(count: usize) -> (usize, usize) {
+    if count == 0 {
+        return (0, 0);
+    }
+
+    let cols = ((count * 2) as f64).sqrt().ceil() as usize;
+    let cols = cols.min(count);
+    (count.div_ceil(cols), cols)
+}
+
+/// Built-in palettes that can be cycled through in the grid
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    /// Material design hues, one per column, accents 50 to 900 down the rows
+    /// (or transposed, see [`PaletteOrder`])
+    #[default]
+    Material,
+    /// Tailwind hues, one per column, shades 50 to 950 down the rows (or
+    /// transposed, see [`PaletteOrder`])
+    Tailwind,
+    /// The 16 standard ANSI colors, normal then bright
+    Ansi16,
+    /// The xterm 256-color palette in index order, 16 per row: the 16 system
+    /// colors, then the 6x6x6 color cube and the 24-step grayscale ramp
+    Ansi256,
+    /// Black to white in even steps
+    Grayscale,
+}
+
+impl Palette {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Material => Self::Tailwind,
+            Self::Tailwind => Self::Ansi16,
+            Self::Ansi16 => Self::Ansi256,
+            Self::Ansi256 => Self::Grayscale,
+            Self::Grayscale => Self::Material,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Material => "Material",
+            Self::Tailwind => "Tailwind",
+            Self::Ansi16 => "ANSI 16",
+            Self::Ansi256 => "ANSI 256",
+            Self::Grayscale => "Grayscale",
+        }
+    }
+
+    /// The palette's colors in row-major order, with its `(rows, cols)` layout
+    pub fn colors(self) -> (Vec<Color>, (usize, usize)) {
+        self.colors_in(PaletteOrder::default())
+    }
+
+    /// Like [`Self::colors`], with the hue palettes arranged in `order`. The
+    /// others have a single natural order and ignore it.
+    pub fn colors_in(self, order: PaletteOrder) -> (Vec<Color>, (usize, usize)) {
+        match self {
+            Self::Material => material_colors(order),
+            Self::Tailwind => tailwind_colors(order),
+            Self::Ansi16 => ((0..16).map(Color::Indexed).collect(), (2, 8)),
+            Self::Ansi256 => ((0..=255).map(Color::Indexed).collect(), (16, 16)),
+            Self::Grayscale => {
+                let colors: Vec<_> = (0..GRAYSCALE_STEPS)
+                    .map(|i| {
+                        let v = (i * 255 / (GRAYSCALE_STEPS - 1)) as u8;
+                        Color::Rgb(v, v, v)
+                    })
+                    .collect();
+                (colors, grid_dimensions(GRAYSCALE_STEPS))
+            }
+        }
+    }
+}
+
+/// How the material and tailwind palettes are laid out in the grid
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PaletteOrder {
+    /// One shade per row, so each column is a hue from lightest to darkest
+    #[default]
+    ShadeRows,
+    /// One hue per row, lightest to darkest, as most palette references are laid out
+    HueRows,
+}
+
+/// Color scheme built by rotating the hue of a base color
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Harmony {
+    /// The base and the hue opposite it
+    #[default]
+    Complementary,
+    /// Three hues evenly spaced around the wheel
+    Triadic,
+    /// The base flanked by its neighbors 30° either side
+    Analogous,
+}
+
+/// Lightness of each row of tints and shades below a harmony's scheme row
+const HARMONY_LIGHTNESS: [u8; 6] = [90, 75, 60, 45, 30, 15];
+
+impl Harmony {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Complementary => Self::Triadic,
+            Self::Triadic => Self::Analogous,
+            Self::Analogous => Self::Complementary,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Complementary => "Complementary",
+            Self::Triadic => "Triadic",
+            Self::Analogous => "Analogous",
+        }
+    }
+
+    /// Degrees each hue of the scheme is turned from the base
+    fn rotations(self) -> &'static [u16] {
+        match self {
+            Self::Complementary => &[0, 180],
+            Self::Triadic => &[0, 120, 240],
+            Self::Analogous => &[330, 0, 30],
+        }
+    }
+
+    /// One column per hue of the scheme. The first row is the scheme itself at
+    /// the base's saturation and lightness, then tints down to shades follow.
+    pub fn colors(self, (r, g, b): (u8, u8, u8)) -> (Vec<Color>, (usize, usize)) {
+        let (h, s, l) = rgb_to_hsl(r, g, b);
+        let rotations = self.rotations();
+        let at = |turn: u16, l: u8| {
+            let (r, g, b) = hsl_to_rgb((h + turn) % 360, s, l);
+            Color::Rgb(r, g, b)
+        };
+
+        // The base keeps its exact RGB rather than a rounded trip through HSL
+        let scheme = rotations.iter().map(|&turn| match turn {
+            0 => Color::Rgb(r, g, b),
+            _ => at(turn, l),
+        });
+        let ramps = HARMONY_LIGHTNESS
+            .into_iter()
+            .flat_map(|l| rotations.iter().map(move |&turn| at(turn, l)));
+
+        let colors = scheme.chain(ramps).collect();
+        (colors, (HARMONY_LIGHTNESS.len() + 1, rotations.len()))
+    }
+}
+
+/// Number of grays from black to white, inclusive
+const GRAYSCALE_STEPS: usize = 32;
+
+/// Every shade of every hue in row-major order for `order`, with the `(rows, cols)` layout
+fn hue_grid<H>(
+    hues: &[&H],
+    shades: &[fn(&H) -> Color],
+    order: PaletteOrder,
+) -> (Vec<Color>, (usize, usize)) {
+    match order {
+        PaletteOrder::ShadeRows => {
+            let colors = shades
+                .iter()
+                .flat_map(|shade| hues.iter().map(|hue| shade(hue)))
+                .collect();
+            (colors, (shades.len(), hues.len()))
+        }
+        PaletteOrder::HueRows => {
+            let colors = hues
+                .iter()
+                .flat_map(|hue| shades.iter().map(|shade| shade(hue)))
+                .collect();
+            (colors, (hues.len(), shades.len()))
+        }
+    }
+}
+
+fn material_colors(order: PaletteOrder) -> (Vec<Color>, (usize, usize)) {
+    let hues = [
+        &material::RED,
+        &material::PINK,
+        &material::PURPLE,
+        &material::DEEP_PURPLE,
+        &material::INDIGO,
+        &material::BLUE,
+        &material::LIGHT_BLUE,
+        &material::CYAN,
+        &material::TEAL,
+        &material::GREEN,
+        &material::LIGHT_GREEN,
+        &material::LIME,
+        &material::YELLOW,
+        &material::AMBER,
+        &material::ORANGE,
+        &material::DEEP_ORANGE,
+    ];
+
+    let accents: [fn(&material::AccentedPalette) -> Color; 10] = [
+        |hue| hue.c50,
+        |hue| hue.c100,
+        |hue| hue.c200,
+        |hue| hue.c300,
+        |hue| hue.c400,
+        |hue| hue.c500,
+        |hue| hue.c600,
+        |hue| hue.c700,
+        |hue| hue.c800,
+        |hue| hue.c900,
+    ];
+
+    hue_grid(&hues, &accents, order)
+}
+
+fn tailwind_colors(order: PaletteOrder) -> (Vec<Color>, (usize, usize)) {
+    let hues = [
+        &tailwind::SLATE,
+        &tailwind::GRAY,
+        &tailwind::ZINC,
+        &tailwind::NEUTRAL,
+        &tailwind::STONE,
+        &tailwind::RED,
+        &tailwind::ORANGE,
+        &tailwind::AMBER,
+        &tailwind::YELLOW,
+        &tailwind::LIME,
+        &tailwind::GREEN,
+        &tailwind::EMERALD,
+        &tailwind::TEAL,
+        &tailwind::CYAN,
+        &tailwind::SKY,
+        &tailwind::BLUE,
+        &tailwind::INDIGO,
+        &tailwind::VIOLET,
+        &tailwind::PURPLE,
+        &tailwind::FUCHSIA,
+        &tailwind::PINK,
+        &tailwind::ROSE,
+    ];
+
+    let shades: [fn(&tailwind::Palette) -> Color; 11] = [
+        |hue| hue.c50,
+        |hue| hue.c100,
+        |hue| hue.c200,
+        |hue| hue.c300,
+        |hue| hue.c400,
+        |hue| hue.c500,
+        |hue| hue.c600,
+        |hue| hue.c700,
+        |hue| hue.c800,
+        |hue| hue.c900,
+        |hue| hue.c950,
+    ];
+
+    hue_grid(&hues, &shades, order)
+}
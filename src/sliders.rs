@@ -0,0 +1,301 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    widgets::{Block, Borders, Gauge, LineGauge, Widget},
+};
+
+use crate::util::{
+    color_support::approximate_rgb,
+    convert::{hsl_to_rgb, hsv_to_rgb, rgb_to_hsl, rgb_to_hsv},
+    styles::Theme,
+};
+
+/// Hue, saturation and value sliders, an alternative to the palette grid
+#[derive(Debug, Default, Clone)]
+pub struct HsvSliders {
+    pub hue: u16,
+    pub saturation: u8,
+    pub value: u8,
+    pub active: Channel,
+    /// Sliders the stepping keys leave alone
+    pub locks: ChannelLocks,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    #[default]
+    Hue,
+    Saturation,
+    Value,
+}
+
+impl Channel {
+    fn next(self) -> Self {
+        match self {
+            Self::Hue => Self::Saturation,
+            Self::Saturation | Self::Value => Self::Value,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::Hue | Self::Saturation => Self::Hue,
+            Self::Value => Self::Saturation,
+        }
+    }
+}
+
+impl HsvSliders {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = hsv_to_rgb(self.hue, self.saturation, self.value);
+        Color::Rgb(r, g, b)
+    }
+
+    /// Indexed and named colors start from their usual xterm values
+    pub fn set_color(&mut self, color: Color) {
+        if let Some((r, g, b)) = approximate_rgb(color) {
+            (self.hue, self.saturation, self.value) = rgb_to_hsv(r, g, b);
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.active = self.active.next();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.active = self.active.prev();
+    }
+
+    /// Move the active slider by `delta`, wrapping hue and clamping the others.
+    /// Locked sliders stay put.
+    pub fn step(&mut self, delta: i16) {
+        if self.locks.contains(self.active) {
+            return;
+        }
+
+        match self.active {
+            Channel::Hue => self.hue = (self.hue as i16 + delta).rem_euclid(360) as u16,
+            Channel::Saturation => self.saturation = step_percent(self.saturation, delta),
+            Channel::Value => self.value = step_percent(self.value, delta),
+        }
+    }
+}
+
+impl From<Channel> for u8 {
+    fn from(channel: Channel) -> Self {
+        channel as u8
+    }
+}
+
+/// Hue, saturation and lightness of the current color, stepped one component
+/// at a time from the gauges shown beside the grid
+#[derive(Debug, Default, Clone)]
+pub struct HslState {
+    pub hue: u16,
+    pub saturation: u8,
+    pub lightness: u8,
+    pub active: HslChannel,
+    /// Components the stepping keys leave alone
+    pub locks: ChannelLocks,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HslChannel {
+    #[default]
+    Hue,
+    Saturation,
+    Lightness,
+}
+
+impl HslChannel {
+    fn next(self) -> Self {
+        match self {
+            Self::Hue => Self::Saturation,
+            Self::Saturation | Self::Lightness => Self::Lightness,
+        }
+    }
+
+    fn prev(self) -> Self {
+        match self {
+            Self::Hue | Self::Saturation => Self::Hue,
+            Self::Lightness => Self::Saturation,
+        }
+    }
+}
+
+impl HslState {
+    pub fn color(&self) -> Color {
+        let (r, g, b) = hsl_to_rgb(self.hue, self.saturation, self.lightness);
+        Color::Rgb(r, g, b)
+    }
+
+    /// Follow `color` unless it's the one these components already make.
+    /// Converting back from RGB each step would round the components and
+    /// lose the hue of grays, so repeated steps would drift.
+    pub fn sync(&mut self, color: Color) {
+        if let Some((r, g, b)) = approximate_rgb(color)
+            && Color::Rgb(r, g, b) != self.color()
+        {
+            (self.hue, self.saturation, self.lightness) = rgb_to_hsl(r, g, b);
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.active = self.active.next();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.active = self.active.prev();
+    }
+
+    /// Move the active component by `delta`, wrapping hue and clamping the
+    /// others. Locked components stay put.
+    pub fn step(&mut self, delta: i16) {
+        if self.locks.contains(self.active) {
+            return;
+        }
+
+        match self.active {
+            HslChannel::Hue => self.hue = (self.hue as i16 + delta).rem_euclid(360) as u16,
+            HslChannel::Saturation => self.saturation = step_percent(self.saturation, delta),
+            HslChannel::Lightness => self.lightness = step_percent(self.lightness, delta),
+        }
+    }
+}
+
+impl From<HslChannel> for u8 {
+    fn from(channel: HslChannel) -> Self {
+        channel as u8
+    }
+}
+
+/// Channels locked against stepping, one bit per channel, shared by the
+/// sliders and the HSL gauges
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelLocks(u8);
+
+impl ChannelLocks {
+    /// Lock `channel`, or unlock it if it's already locked
+    pub fn toggle(&mut self, channel: impl Into<u8>) {
+        self.0 ^= 1 << channel.into();
+    }
+
+    pub fn contains(self, channel: impl Into<u8>) -> bool {
+        self.0 & (1 << channel.into()) != 0
+    }
+}
+
+fn step_percent(value: u8, delta: i16) -> u8 {
+    (i16::from(value) + delta).clamp(0, 100) as u8
+}
+
+/// Marks the label of a locked slider or gauge
+const LOCK_GLYPH: &str = " 🔒";
+
+/// `label`, with the lock glyph after it when `locked`
+fn lock_label(label: String, locked: bool) -> String {
+    if locked { label + LOCK_GLYPH } else { label }
+}
+
+pub struct HsvSlidersWidget<'a> {
+    pub sliders: &'a HsvSliders,
+    pub focused: bool,
+    pub theme: Theme,
+}
+
+impl Widget for HsvSlidersWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let sliders = self.sliders;
+        let (hr, hg, hb) = hsv_to_rgb(sliders.hue, 100, 100);
+
+        let rows = [
+            (
+                Channel::Hue,
+                format!("Hue {}°", sliders.hue),
+                f64::from(sliders.hue) / 360.0,
+                Color::Rgb(hr, hg, hb),
+            ),
+            (
+                Channel::Saturation,
+                format!("Saturation {}%", sliders.saturation),
+                f64::from(sliders.saturation) / 100.0,
+                sliders.color(),
+            ),
+            (
+                Channel::Value,
+                format!("Value {}%", sliders.value),
+                f64::from(sliders.value) / 100.0,
+                sliders.color(),
+            ),
+        ];
+
+        let areas = Layout::vertical([Constraint::Length(3); 3]).split(area);
+
+        for ((channel, label, ratio, color), area) in rows.into_iter().zip(areas.iter()) {
+            let active = self.focused && sliders.active == channel;
+            let label = lock_label(label, sliders.locks.contains(channel));
+
+            Gauge::default()
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(self.theme.focus_border(active)),
+                )
+                .gauge_style(Style::default().fg(color))
+                .label(label)
+                .ratio(ratio)
+                .render(*area, buf);
+        }
+    }
+}
+
+/// One-line gauges for each HSL component, compact enough to sit under the grid
+pub struct HslGaugesWidget<'a> {
+    pub state: &'a HslState,
+    pub focused: bool,
+    pub theme: Theme,
+}
+
+impl Widget for HslGaugesWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let state = self.state;
+        let (hr, hg, hb) = hsl_to_rgb(state.hue, 100, 50);
+
+        let rows = [
+            (
+                HslChannel::Hue,
+                format!("H {:>3}°", state.hue),
+                f64::from(state.hue) / 360.0,
+                Color::Rgb(hr, hg, hb),
+            ),
+            (
+                HslChannel::Saturation,
+                format!("S {:>3}%", state.saturation),
+                f64::from(state.saturation) / 100.0,
+                state.color(),
+            ),
+            (
+                HslChannel::Lightness,
+                format!("L {:>3}%", state.lightness),
+                f64::from(state.lightness) / 100.0,
+                state.color(),
+            ),
+        ];
+
+        let areas = Layout::vertical([Constraint::Length(1); 3]).split(area);
+
+        for ((channel, label, ratio, color), area) in rows.into_iter().zip(areas.iter()) {
+            let active = self.focused && state.active == channel;
+            let label = lock_label(label, state.locks.contains(channel));
+
+            LineGauge::default()
+                .label(label)
+                .style(self.theme.focus_border(active))
+                .filled_style(Style::default().fg(color))
+                .unfilled_style(self.theme.muted())
+                .ratio(ratio)
+                .render(*area, buf);
+        }
+    }
+}
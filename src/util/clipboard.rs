@@ -0,0 +1,55 @@
+use std::{
+    env,
+    process::{Command, Stdio},
+};
+
+use arboard::Clipboard;
+
+/// Read text from the system clipboard, if available
+pub fn get_text() -> Option<String> {
+    Clipboard::new().ok()?.get_text().ok()
+}
+
+/// Write text to the system clipboard
+pub fn set_text(text: String) -> Result<(), arboard::Error> {
+    Clipboard::new()?.set_text(text)
+}
+
+/// Hidden flag that runs the binary as a clipboard holder, see [`set_text_after_exit`]
+pub const HOLD_FLAG: &str = "--hold-clipboard";
+
+/// Write text to the clipboard so it's still there once this process exits.
+/// On X11 and Wayland the clipboard is served by the process that set it, so
+/// this hands the text to a copy of the binary running [`hold`] in the
+/// background. Elsewhere the system keeps the text itself.
+pub fn set_text_after_exit(text: String) -> Result<(), arboard::Error> {
+    if !cfg!(all(unix, not(target_os = "macos"))) {
+        return set_text(text);
+    }
+
+    // Fail here, where the error can still be reported, if there's no clipboard
+    Clipboard::new()?;
+    let spawned = env::current_exe().and_then(|exe| {
+        Command::new(exe)
+            .args([HOLD_FLAG, &text])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+
+    spawned.map(drop).map_err(|err| arboard::Error::Unknown {
+        description: format!("couldn't start the clipboard holder: {err}"),
+    })
+}
+
+/// Set the clipboard and keep serving it until something else is copied
+pub fn hold(text: String) -> Result<(), arboard::Error> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use arboard::SetExtLinux;
+        Clipboard::new()?.set().wait().text(text)
+    }
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    set_text(text)
+}
@@ -0,0 +1,153 @@
+use std::env;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Color};
+
+use crate::util::convert::rgb_distance;
+
+/// How many colors the terminal can show
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    #[default]
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// Channel levels of the xterm 6x6x6 color cube, indices 16-231
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// xterm's default RGB values for the 16 basic colors
+const ANSI_16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+impl ColorSupport {
+    /// Guess from `COLORTERM` and `TERM`. Terminals that don't advertise
+    /// truecolor are assumed to have 256 colors if `TERM` says so, else 16.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        if matches!(colorterm.as_str(), "truecolor" | "24bit")
+            || env::var_os("WT_SESSION").is_some()
+        {
+            return Self::TrueColor;
+        }
+
+        match env::var("TERM") {
+            Ok(term) if term.contains("256color") => Self::Ansi256,
+            _ => Self::Ansi16,
+        }
+    }
+
+    /// Nearest color the terminal can show; indexed and named colors pass through
+    pub fn quantize(self, color: Color) -> Color {
+        let Color::Rgb(r, g, b) = color else {
+            return color;
+        };
+
+        match self {
+            Self::TrueColor => color,
+            Self::Ansi256 => Color::Indexed(nearest_256((r, g, b))),
+            Self::Ansi16 => Color::Indexed(nearest_in(&ANSI_16, (r, g, b))),
+        }
+    }
+
+    /// Quantize the foreground and background of every cell in `area`
+    pub fn quantize_buffer(self, area: Rect, buf: &mut Buffer) {
+        if self == Self::TrueColor {
+            return;
+        }
+
+        for position in area.intersection(buf.area).positions() {
+            let cell = &mut buf[position];
+            cell.fg = self.quantize(cell.fg);
+            cell.bg = self.quantize(cell.bg);
+        }
+    }
+}
+
+/// RGB of any color using xterm's defaults for indexed and named colors, which
+/// real terminals only approximate. `None` for `Reset`, whose color is unknown.
+pub fn approximate_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    let index = match color {
+        Color::Rgb(r, g, b) => return Some((r, g, b)),
+        Color::Reset => return None,
+        Color::Indexed(index) => index,
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+    };
+
+    Some(match index {
+        0..16 => ANSI_16[usize::from(index)],
+        16..232 => {
+            let i = usize::from(index - 16);
+            (
+                CUBE_LEVELS[i / 36],
+                CUBE_LEVELS[i / 6 % 6],
+                CUBE_LEVELS[i % 6],
+            )
+        }
+        _ => {
+            let v = 8 + 10 * (index - 232);
+            (v, v, v)
+        }
+    })
+}
+
+/// Closest 256-color index, picking between the color cube and the gray ramp
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| nearest_in(&CUBE_LEVELS.map(|l| (l, l, l)), (v, v, v));
+    let (ri, gi, bi) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (
+        CUBE_LEVELS[usize::from(ri)],
+        CUBE_LEVELS[usize::from(gi)],
+        CUBE_LEVELS[usize::from(bi)],
+    );
+
+    // The 24 grays from 8 to 238 fill the gaps between the cube's grays
+    let average = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_value = 8 + 10 * gray_step;
+    let gray = (gray_value, gray_value, gray_value);
+
+    if rgb_distance(rgb, gray) < rgb_distance(rgb, cube) {
+        232 + gray_step
+    } else {
+        16 + 36 * ri + 6 * gi + bi
+    }
+}
+
+/// Index of the entry in `colors` closest to `rgb`
+fn nearest_in(colors: &[(u8, u8, u8)], rgb: (u8, u8, u8)) -> u8 {
+    (0..colors.len())
+        .min_by_key(|&i| rgb_distance(rgb, colors[i]))
+        .unwrap_or_default() as u8
+}
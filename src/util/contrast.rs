@@ -0,0 +1,66 @@
+use ratatui::style::Color;
+
+use crate::util::{color_support::approximate_rgb, convert::srgb_to_linear};
+
+/// WCAG relative luminance of an sRGB color, in `0.0..=1.0`
+pub fn relative_luminance(r: u8, g: u8, b: u8) -> f64 {
+    0.2126 * srgb_to_linear(r) + 0.7152 * srgb_to_linear(g) + 0.0722 * srgb_to_linear(b)
+}
+
+/// WCAG contrast ratio between two luminances, from 1:1 up to 21:1
+pub fn contrast_ratio(a: f64, b: f64) -> f64 {
+    let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// Minimum ratio for normal text at WCAG level AA
+pub const AA_RATIO: f64 = 4.5;
+/// Minimum ratio for normal text at WCAG level AAA
+pub const AAA_RATIO: f64 = 7.0;
+
+/// Black or white, whichever contrasts more with `color`. Indexed and named
+/// colors are judged by their usual xterm values.
+pub fn contrasting_color(color: Color) -> Color {
+    let Some((r, g, b)) = approximate_rgb(color) else {
+        return Color::White;
+    };
+
+    let luminance = relative_luminance(r, g, b);
+    if contrast_ratio(luminance, 0.0) > contrast_ratio(luminance, 1.0) {
+        Color::Black
+    } else {
+        Color::White
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_is_dark_on_light_and_light_on_dark() {
+        let light = [
+            Color::Rgb(255, 255, 255),
+            Color::Rgb(0xFA, 0xFA, 0xFA),
+            Color::Rgb(0xFF, 0xEB, 0xEE),
+            Color::Rgb(0xFF, 0xFF, 0x00),
+            Color::White,
+            Color::Indexed(231),
+        ];
+        let dark = [
+            Color::Rgb(0, 0, 0),
+            Color::Rgb(0x10, 0x10, 0x10),
+            Color::Rgb(0x00, 0x00, 0x80),
+            Color::Rgb(0x4A, 0x14, 0x8C),
+            Color::Black,
+            Color::Indexed(16),
+        ];
+
+        for color in light {
+            assert_eq!(contrasting_color(color), Color::Black, "{color:?}");
+        }
+        for color in dark {
+            assert_eq!(contrasting_color(color), Color::White, "{color:?}");
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use ratatui::style::Color;
+
+/// Parse `RRGGBB` or the shorthand `RGB`, with or without a leading `#`
+pub fn hex_to_rgb(hex: &str) -> Option<(u8, u8, u8)> {
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..=i], 16).ok();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => Some((digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+        6 => Some((pair(0)?, pair(2)?, pair(4)?)),
+        _ => None,
+    }
+}
+
+/// Format RGB channels as `#RRGGBB`
+pub fn rgb_to_hex(r: u8, g: u8, b: u8) -> String {
+    format!("#{}", rgb_to_hex_bare(r, g, b))
+}
+
+/// Format RGB channels as `RRGGBB`, the form the hex input holds
+pub fn rgb_to_hex_bare(r: u8, g: u8, b: u8) -> String {
+    format!("{r:02X}{g:02X}{b:02X}")
+}
+
+/// Convert RGB channels to HSL (hue in degrees, saturation and lightness in percent)
+pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
+    let (r, g, b) = normalize(r, g, b);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let l = (max + min) / 2.0;
+
+    let s = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * l - 1.0).abs())
+    };
+
+    (hue(r, g, b), to_percent(s), to_percent(l))
+}
+
+/// Convert HSL (hue in degrees, saturation and lightness in percent) to RGB channels
+pub fn hsl_to_rgb(h: u16, s: u8, l: u8) -> (u8, u8, u8) {
+    let s = from_percent(s);
+    let l = from_percent(l);
+
+    let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    from_hue_chroma(h, chroma, l - chroma / 2.0)
+}
+
+/// Convert RGB channels to HSV (hue in degrees, saturation and value in percent)
+pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (u16, u8, u8) {
+    let (r, g, b) = normalize(r, g, b);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+
+    let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+
+    (hue(r, g, b), to_percent(s), to_percent(max))
+}
+
+/// Convert HSV (hue in degrees, saturation and value in percent) to RGB channels
+pub fn hsv_to_rgb(h: u16, s: u8, v: u8) -> (u8, u8, u8) {
+    let s = from_percent(s);
+    let v = from_percent(v);
+
+    let chroma = v * s;
+    from_hue_chroma(h, chroma, v - chroma)
+}
+
+/// Convert RGB channels to CMYK percentages. Without a color profile this is
+/// only an approximation of what a printer would use.
+pub fn rgb_to_cmyk(r: u8, g: u8, b: u8) -> (u8, u8, u8, u8) {
+    let (r, g, b) = normalize(r, g, b);
+    let k = 1.0 - r.max(g).max(b);
+
+    if k == 1.0 {
+        return (0, 0, 0, 100);
+    }
+
+    let ink = |v: f64| to_percent((1.0 - v - k) / (1.0 - k));
+    (ink(r), ink(g), ink(b), to_percent(k))
+}
+
+/// Convert CMYK percentages to RGB channels
+pub fn cmyk_to_rgb(c: u8, m: u8, y: u8, k: u8) -> (u8, u8, u8) {
+    let k = from_percent(k);
+    let channel = |ink: u8| (255.0 * (1.0 - from_percent(ink)) * (1.0 - k)).round() as u8;
+    (channel(c), channel(m), channel(y))
+}
+
+fn normalize(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    (
+        f64::from(r) / 255.0,
+        f64::from(g) / 255.0,
+        f64::from(b) / 255.0,
+    )
+}
+
+fn to_percent(v: f64) -> u8 {
+    (v * 100.0).round() as u8
+}
+
+fn from_percent(v: u8) -> f64 {
+    f64::from(v.min(100)) / 100.0
+}
+
+/// Hue in degrees shared by HSL and HSV, from normalized RGB
+fn hue(r: f64, g: f64, b: f64) -> u16 {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+
+    let h = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (h.round() as u16) % 360
+}
+
+/// Rebuild RGB channels from a hue, its chroma and the lightness offset `m`
+fn from_hue_chroma(h: u16, chroma: f64, m: f64) -> (u8, u8, u8) {
+    let h = f64::from(h % 360);
+    let x = chroma * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+
+    let (r, g, b) = match h as u16 {
+        0..60 => (chroma, x, 0.0),
+        60..120 => (x, chroma, 0.0),
+        120..180 => (0.0, chroma, x),
+        180..240 => (0.0, x, chroma),
+        240..300 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let to_channel = |v: f64| ((v + m) * 255.0).round() as u8;
+    (to_channel(r), to_channel(g), to_channel(b))
+}
+
+/// Linearly interpolate between two RGB colors in sRGB space, `t` in `0.0..=1.0`
+pub fn mix_rgb(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let lerp = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+    (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
+
+/// Interpolate between two RGB colors in linear light, which avoids the muddy
+/// midpoints of blending sRGB directly
+pub fn mix_linear(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let lerp = |a: u8, b: u8| {
+        let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+        linear_to_srgb(a + (b - a) * t)
+    };
+    (lerp(from.0, to.0), lerp(from.1, to.1), lerp(from.2, to.2))
+}
+
+/// Decode an sRGB channel to linear light, in `0.0..=1.0`
+pub fn srgb_to_linear(channel: u8) -> f64 {
+    let c = f64::from(channel) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encode a linear-light value back to an sRGB channel, clamping out-of-gamut values
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let v = value.clamp(0.0, 1.0);
+    let c = if v <= 0.003_130_8 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+/// Convert RGB channels to CIE L*a*b* under the D65 white point
+pub fn rgb_to_lab(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b));
+    let x = (0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b) / 0.950_47;
+    let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175_0 * b;
+    let z = (0.019_333_9 * r + 0.119_192_0 * g + 0.950_304_1 * b) / 1.088_83;
+
+    let f = |t: f64| {
+        if t > 216.0 / 24_389.0 {
+            t.cbrt()
+        } else {
+            (24_389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Squared Euclidean distance between two colors in RGB space, which is
+/// enough to compare which of several colors is closest
+pub(crate) fn rgb_distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> i32 {
+    let dr = i32::from(a.0) - i32::from(b.0);
+    let dg = i32::from(a.1) - i32::from(b.1);
+    let db = i32::from(a.2) - i32::from(b.2);
+    dr * dr + dg * dg + db * db
+}
+
+/// CIE76 color difference: the distance between two colors in L*a*b*. Around
+/// 2.3 is the smallest difference most people can see.
+pub fn delta_e(from: (u8, u8, u8), to: (u8, u8, u8)) -> f64 {
+    let (l1, a1, b1) = rgb_to_lab(from.0, from.1, from.2);
+    let (l2, a2, b2) = rgb_to_lab(to.0, to.1, to.2);
+    ((l2 - l1).powi(2) + (a2 - a1).powi(2) + (b2 - b1).powi(2)).sqrt()
+}
+
+/// RGB channels of `color`, or `None` for indexed and named colors
+pub fn rgb_components(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Primaries, secondaries, black and white: every channel at 0 or 255
+    const EDGES: [(u8, u8, u8); 8] = [
+        (0, 0, 0),
+        (255, 255, 255),
+        (255, 0, 0),
+        (0, 255, 0),
+        (0, 0, 255),
+        (255, 255, 0),
+        (0, 255, 255),
+        (255, 0, 255),
+    ];
+
+    fn via_hsl((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let (h, s, l) = rgb_to_hsl(r, g, b);
+        hsl_to_rgb(h, s, l)
+    }
+
+    fn via_cmyk((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+        let (c, m, y, k) = rgb_to_cmyk(r, g, b);
+        cmyk_to_rgb(c, m, y, k)
+    }
+
+    /// Largest difference between two colors' channels
+    fn channel_error(from: (u8, u8, u8), to: (u8, u8, u8)) -> u8 {
+        [
+            from.0.abs_diff(to.0),
+            from.1.abs_diff(to.1),
+            from.2.abs_diff(to.2),
+        ]
+        .into_iter()
+        .max()
+        .unwrap()
+    }
+
+    #[test]
+    fn hex_round_trips_exactly() {
+        let grays = (0..=255).map(|v| (v, v, v));
+        for (r, g, b) in EDGES.into_iter().chain(grays) {
+            let hex = rgb_to_hex(r, g, b);
+            assert_eq!(hex_to_rgb(&hex), Some((r, g, b)), "{hex}");
+            assert_eq!(
+                hex_to_rgb(&rgb_to_hex_bare(r, g, b)),
+                Some((r, g, b)),
+                "{hex}"
+            );
+        }
+    }
+
+    #[test]
+    fn edges_round_trip_exactly_through_hsl_and_cmyk() {
+        for rgb in EDGES {
+            let hex = rgb_to_hex(rgb.0, rgb.1, rgb.2);
+            let parsed = hex_to_rgb(&hex).unwrap();
+
+            assert_eq!(via_hsl(parsed), rgb, "{hex} through HSL");
+            assert_eq!(via_cmyk(parsed), rgb, "{hex} through CMYK");
+        }
+    }
+
+    #[test]
+    fn known_edges_convert_to_known_values() {
+        assert_eq!(rgb_to_hsl(255, 0, 0), (0, 100, 50));
+        assert_eq!(rgb_to_hsl(0, 255, 0), (120, 100, 50));
+        assert_eq!(rgb_to_hsl(0, 0, 255), (240, 100, 50));
+        assert_eq!(rgb_to_hsl(0, 0, 0), (0, 0, 0));
+        assert_eq!(rgb_to_hsl(255, 255, 255), (0, 0, 100));
+
+        assert_eq!(rgb_to_cmyk(255, 0, 0), (0, 100, 100, 0));
+        assert_eq!(rgb_to_cmyk(0, 0, 0), (0, 0, 0, 100));
+        assert_eq!(rgb_to_cmyk(255, 255, 255), (0, 0, 0, 0));
+        assert_eq!(cmyk_to_rgb(0, 0, 0, 100), (0, 0, 0));
+        assert_eq!(cmyk_to_rgb(100, 0, 100, 0), (0, 255, 0));
+    }
+
+    #[test]
+    fn grays_stay_gray_within_a_percent() {
+        // HSL lightness and CMYK key are whole percents, so a gray may land on
+        // a neighbouring level, but never gains a hue
+        for v in 0..=255 {
+            let gray = (v, v, v);
+            for (via, back) in [("HSL", via_hsl(gray)), ("CMYK", via_cmyk(gray))] {
+                assert!(
+                    back.0 == back.1 && back.1 == back.2,
+                    "{gray:?} via {via} gave {back:?}"
+                );
+                assert!(
+                    channel_error(gray, back) <= 1,
+                    "{gray:?} via {via} gave {back:?}"
+                );
+            }
+        }
+    }
+}
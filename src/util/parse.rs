@@ -0,0 +1,140 @@
+use ratatui::style::Color;
+
+use crate::util::{
+    convert::{cmyk_to_rgb, hex_to_rgb, hsl_to_rgb},
+    named_colors::by_name,
+};
+
+/// Parse a color in any of the forms the picker understands, ignoring case and
+/// surrounding whitespace:
+///
+/// - hex as `RGB`, `RRGGBB` or `RRGGBBAA`, with or without a leading `#`
+/// - `rgb(r, g, b)` with channels from 0 to 255
+/// - a CSS color name such as `rebeccapurple`
+///
+/// Returns the color and, for `RRGGBBAA`, its alpha.
+pub fn parse_color(input: &str) -> Option<(Color, Option<u8>)> {
+    let input = input.trim();
+
+    if let Some(args) = strip_function(input, "rgb") {
+        return parse_rgb(args).map(|color| (color, None));
+    }
+
+    parse_hex(input).or_else(|| by_name(input).map(|color| (color, None)))
+}
+
+/// Parse a hex color, with or without a leading `#`, expanding the CSS shorthand
+/// `RGB` to `RRGGBB`
+pub fn parse_hex(input: &str) -> Option<(Color, Option<u8>)> {
+    let hex = input.strip_prefix('#').unwrap_or(input);
+    // `from_str_radix` would also take a sign
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let (rgb, alpha) = match hex.len() {
+        8 => {
+            let alpha = hex.get(6..).and_then(|a| u8::from_str_radix(a, 16).ok())?;
+            (hex_to_rgb(hex.get(..6)?)?, Some(alpha))
+        }
+        _ => (hex_to_rgb(hex)?, None),
+    };
+    let (r, g, b) = rgb;
+    Some((Color::Rgb(r, g, b), alpha))
+}
+
+/// Comma-separated RGB channels. Rejects any channel above 255.
+pub fn parse_rgb(input: &str) -> Option<Color> {
+    let [r, g, b] = parse_components(input)?;
+    Some(Color::Rgb(
+        u8::try_from(r).ok()?,
+        u8::try_from(g).ok()?,
+        u8::try_from(b).ok()?,
+    ))
+}
+
+/// Comma-separated HSL. Clamps hue to 0-360 and saturation/lightness to 0-100.
+pub fn parse_hsl(input: &str) -> Option<Color> {
+    let [h, s, l] = parse_components(input)?;
+    let (r, g, b) = hsl_to_rgb(h.min(360) as u16, s.min(100) as u8, l.min(100) as u8);
+    Some(Color::Rgb(r, g, b))
+}
+
+/// Comma-separated CMYK percentages. Rejects any component above 100.
+pub fn parse_cmyk(input: &str) -> Option<Color> {
+    let [c, m, y, k] = parse_components(input)?;
+    let percent = |v: u64| u8::try_from(v).ok().filter(|&v| v <= 100);
+    let (r, g, b) = cmyk_to_rgb(percent(c)?, percent(m)?, percent(y)?, percent(k)?);
+    Some(Color::Rgb(r, g, b))
+}
+
+/// Split `input` into exactly `N` comma-separated decimal components
+fn parse_components<const N: usize>(input: &str) -> Option<[u64; N]> {
+    let mut values = [0; N];
+    let mut parts = input.split(',');
+
+    for value in &mut values {
+        *value = parts.next()?.trim().parse().ok()?;
+    }
+
+    parts.next().is_none().then_some(values)
+}
+
+/// The arguments of a CSS-style call like `rgb(1, 2, 3)`, if `input` is a call to `name`
+fn strip_function<'a>(input: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = input.get(..name.len())?;
+    if !prefix.eq_ignore_ascii_case(name) {
+        return None;
+    }
+
+    input[name.len()..]
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_color_accepts_hex_rgb_calls_and_names() {
+        let cases = [
+            ("#abc", Some((Color::Rgb(0xAA, 0xBB, 0xCC), None))),
+            ("abc", Some((Color::Rgb(0xAA, 0xBB, 0xCC), None))),
+            (
+                "#AABBCCDD",
+                Some((Color::Rgb(0xAA, 0xBB, 0xCC), Some(0xDD))),
+            ),
+            ("  #ff8800  ", Some((Color::Rgb(0xFF, 0x88, 0x00), None))),
+            ("rgb( 1 , 2 , 3 )", Some((Color::Rgb(1, 2, 3), None))),
+            ("RGB(1,2,3)", Some((Color::Rgb(1, 2, 3), None))),
+            ("rebeccapurple", Some((Color::Rgb(0x66, 0x33, 0x99), None))),
+            ("RebeccaPurple", Some((Color::Rgb(0x66, 0x33, 0x99), None))),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse_color(input), expected, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn parse_color_rejects_malformed_input() {
+        let cases = [
+            "rgb(256,0,0)",
+            "rgb(1,2)",
+            "rgb(1,2,3,4)",
+            "rgb(1,2,3",
+            "#ff88",
+            "#AABBCC+F",
+            "#ggg",
+            "notacolor",
+            "",
+            "   ",
+        ];
+
+        for input in cases {
+            assert_eq!(parse_color(input), None, "{input:?}");
+        }
+    }
+}
@@ -1,34 +1,126 @@
+use std::str::FromStr;
+
+use color_eyre::{Report, eyre::eyre};
 use ratatui::style::{Color, Style};
 
-pub struct Styles;
+use crate::color_input::Validity;
+
+/// Colors for the picker's chrome. Swatches always show their true colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Modal background and the text drawn on it
+    pub background: Color,
+    pub text: Color,
+    /// Secondary text such as the hint bar and key hints on buttons
+    pub muted: Color,
+    /// Borders of unfocused sections
+    pub border: Color,
+    /// Border of the focused section
+    pub focus: Color,
+    /// Brighter half of the focused button's pulse
+    pub pulse: Color,
+    pub valid: Color,
+    pub invalid: Color,
+    /// Button background and text
+    pub button: (Color, Color),
+    pub button_selected: (Color, Color),
+    /// Light and dark squares of the checkerboard drawn behind transparent
+    /// colors, as RGB so the color on top can be blended with them
+    pub checker_light: (u8, u8, u8),
+    pub checker_dark: (u8, u8, u8),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
 
-impl Styles {
-    /// Get border color based on focus state and validity
-    pub fn border_color(focused: bool, is_valid: Option<bool>) -> Color {
-        match (focused, is_valid) {
-            (true, _) => Color::Cyan,
-            (false, Some(true)) => Color::Green,
-            (false, Some(false)) => Color::Red,
-            (false, None) => Color::Reset,
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::DarkGray,
+            text: Color::Reset,
+            muted: Color::Gray,
+            border: Color::Reset,
+            focus: Color::Cyan,
+            pulse: Color::LightCyan,
+            valid: Color::Green,
+            invalid: Color::Red,
+            button: (Color::DarkGray, Color::Gray),
+            button_selected: (Color::Blue, Color::White),
+            checker_light: (0xCC, 0xCC, 0xCC),
+            checker_dark: (0x99, 0x99, 0x99),
+        }
+    }
+
+    /// For light terminals, where the dark gray modal looks out of place
+    pub fn light() -> Self {
+        Self {
+            background: Color::White,
+            text: Color::Black,
+            muted: Color::DarkGray,
+            border: Color::Black,
+            focus: Color::Blue,
+            pulse: Color::LightBlue,
+            valid: Color::Green,
+            invalid: Color::Red,
+            button: (Color::Gray, Color::Black),
+            button_selected: (Color::Blue, Color::White),
+            checker_light: (0xFF, 0xFF, 0xFF),
+            checker_dark: (0xDD, 0xDD, 0xDD),
+        }
+    }
+
+    /// Get border color based on focus state and validity. Input that's
+    /// merely unfinished stays neutral rather than flagged red.
+    pub fn border_color(&self, focused: bool, validity: Validity) -> Color {
+        match (focused, validity) {
+            (true, _) => self.focus,
+            (false, Validity::Valid) => self.valid,
+            (false, Validity::Invalid) => self.invalid,
+            (false, Validity::Empty | Validity::Incomplete) => self.border,
         }
     }
 
     /// Get button colors based on state
-    pub fn button_colors(selected: bool) -> (Color, Color) {
+    pub fn button_colors(&self, selected: bool) -> (Color, Color) {
         if selected {
-            (Color::Blue, Color::White)
+            self.button_selected
         } else {
-            (Color::DarkGray, Color::Gray)
+            self.button
         }
     }
 
     /// Common modal background style
-    pub fn modal_background() -> Style {
-        Style::default().bg(Color::DarkGray)
+    pub fn modal_background(&self) -> Style {
+        Style::default().bg(self.background).fg(self.text)
+    }
+
+    /// Style for secondary text
+    pub fn muted(&self) -> Style {
+        Style::default().fg(self.muted)
     }
 
     /// Focus border style
-    pub fn focus_border(focused: bool) -> Style {
-        Style::default().fg(if focused { Color::Cyan } else { Color::Reset })
+    pub fn focus_border(&self, focused: bool) -> Style {
+        Style::default().fg(if focused { self.focus } else { self.border })
+    }
+
+    /// Brighter half of the focused button's pulse
+    pub fn pulse_border(&self) -> Style {
+        Style::default().fg(self.pulse)
+    }
+}
+
+impl FromStr for Theme {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "dark" => Ok(Self::dark()),
+            "light" => Ok(Self::light()),
+            _ => Err(eyre!("unknown theme `{s}`, expected one of: dark, light")),
+        }
     }
 }
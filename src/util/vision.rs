@@ -0,0 +1,69 @@
+use ratatui::style::Color;
+
+use crate::util::convert::{linear_to_srgb, srgb_to_linear};
+
+/// Color vision deficiency simulated when rendering swatches
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VisionMode {
+    #[default]
+    Normal,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl VisionMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Normal => Self::Protanopia,
+            Self::Protanopia => Self::Deuteranopia,
+            Self::Deuteranopia => Self::Tritanopia,
+            Self::Tritanopia => Self::Normal,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Protanopia => "Protanopia",
+            Self::Deuteranopia => "Deuteranopia",
+            Self::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// Machado et al. (2009) simulation matrices at full severity, in linear RGB
+    fn matrix(self) -> Option<[[f64; 3]; 3]> {
+        match self {
+            Self::Normal => None,
+            Self::Protanopia => Some([
+                [0.152_286, 1.052_583, -0.204_868],
+                [0.114_503, 0.786_281, 0.099_216],
+                [-0.003_882, -0.048_116, 1.051_998],
+            ]),
+            Self::Deuteranopia => Some([
+                [0.367_322, 0.860_646, -0.227_968],
+                [0.280_085, 0.672_501, 0.047_413],
+                [-0.011_820, 0.042_940, 0.968_881],
+            ]),
+            Self::Tritanopia => Some([
+                [1.255_528, -0.076_749, -0.178_779],
+                [-0.078_411, 0.930_809, 0.147_602],
+                [0.004_733, 0.691_367, 0.303_900],
+            ]),
+        }
+    }
+
+    /// How `color` appears under this mode; non-RGB colors pass through unchanged
+    pub fn simulate(self, color: Color) -> Color {
+        let (Some(matrix), Color::Rgb(r, g, b)) = (self.matrix(), color) else {
+            return color;
+        };
+
+        let linear = [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b)];
+        let channel = |row: [f64; 3]| {
+            linear_to_srgb(row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+        };
+
+        Color::Rgb(channel(matrix[0]), channel(matrix[1]), channel(matrix[2]))
+    }
+}
@@ -0,0 +1,166 @@
+//! The picker draws on the terminal through stderr, so stdout carries only the
+//! applied color and `$(color-picker)` or `| jq` see nothing else.
+
+use std::{
+    fs,
+    io::Write,
+    path::PathBuf,
+    process::{self, Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+/// Run the picker with `args` on a pseudo-terminal from util-linux `script`,
+/// with stdout redirected to a file, and type `keys` once it's up. Returns the
+/// exit code and what landed on stdout, or `None` where `script` is missing.
+fn run_on_pty(name: &str, args: &str, keys: &[u8]) -> Option<(i32, String)> {
+    let dir = temp_dir(name);
+    let out = dir.join("stdout");
+    let command = format!(
+        "stty rows 40 cols 120; {} {args} > {}",
+        env!("CARGO_BIN_EXE_color-picker-ratatui"),
+        out.display()
+    );
+
+    let mut child = Command::new("script")
+        .args(["-qec", &command, "/dev/null"])
+        // Keep the test away from the real config and clipboard
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .env_remove("DISPLAY")
+        .env_remove("WAYLAND_DISPLAY")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    thread::sleep(Duration::from_millis(500));
+    let mut stdin = child.stdin.take()?;
+    stdin.write_all(keys).ok()?;
+    stdin.flush().ok()?;
+
+    // A picker still up after the keys is a failure, not a hung test
+    let mut waited = Duration::ZERO;
+    let status = loop {
+        if let Some(status) = child.try_wait().ok()? {
+            break status;
+        }
+        if waited > Duration::from_secs(10) {
+            let _ = child.kill();
+            panic!("the picker didn't exit after {keys:?}");
+        }
+        thread::sleep(Duration::from_millis(50));
+        waited += Duration::from_millis(50);
+    };
+    drop(stdin);
+
+    let stdout = fs::read_to_string(&out).unwrap_or_default();
+    let _ = fs::remove_dir_all(&dir);
+    Some((status.code()?, stdout))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("color-picker-{}-{name}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A `#RRGGBB` line and nothing else
+fn assert_one_hex_line(stdout: &str) {
+    let hex = stdout
+        .strip_prefix('#')
+        .and_then(|rest| rest.strip_suffix('\n'));
+    assert!(
+        hex.is_some_and(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit())),
+        "stdout isn't one hex color: {stdout:?}"
+    );
+}
+
+/// Exactly one JSON document, followed by a newline and nothing else
+fn assert_one_json_document(stdout: &str) {
+    let mut documents = serde_json::Deserializer::from_str(stdout).into_iter::<serde_json::Value>();
+    assert!(
+        documents.next().is_some_and(|document| document.is_ok()),
+        "stdout isn't JSON: {stdout:?}"
+    );
+    assert_eq!(
+        &stdout[documents.byte_offset()..],
+        "\n",
+        "more than JSON: {stdout:?}"
+    );
+}
+
+#[test]
+fn applied_hex_is_all_of_stdout() {
+    // Shift+Tab back around to Apply and press it
+    let Some((code, stdout)) = run_on_pty("apply", "", b"\x1b[Z\x1b[Z\r") else {
+        eprintln!("skipped: no `script` to make a terminal with");
+        return;
+    };
+
+    assert_eq!(code, 0);
+    assert_one_hex_line(&stdout);
+}
+
+#[test]
+fn inline_hex_is_all_of_stdout() {
+    let Some((code, stdout)) = run_on_pty("inline", "--no-alt-screen", b"\x1b[Z\x1b[Z\r") else {
+        eprintln!("skipped: no `script` to make a terminal with");
+        return;
+    };
+
+    assert_eq!(code, 0);
+    assert_one_hex_line(&stdout);
+}
+
+#[test]
+fn applied_json_is_all_of_stdout() {
+    let Some((code, stdout)) = run_on_pty("apply-json", "--format json", b"\x1b[Z\x1b[Z\r") else {
+        eprintln!("skipped: no `script` to make a terminal with");
+        return;
+    };
+
+    assert_eq!(code, 0);
+    assert_one_json_document(&stdout);
+}
+
+#[test]
+fn inline_json_is_all_of_stdout() {
+    let Some((code, stdout)) = run_on_pty(
+        "inline-json",
+        "--format json --no-alt-screen",
+        b"\x1b[Z\x1b[Z\r",
+    ) else {
+        eprintln!("skipped: no `script` to make a terminal with");
+        return;
+    };
+
+    assert_eq!(code, 0);
+    assert_one_json_document(&stdout);
+}
+
+#[test]
+fn quitting_prints_nothing() {
+    let Some((code, stdout)) = run_on_pty("quit", "", b"q") else {
+        eprintln!("skipped: no `script` to make a terminal with");
+        return;
+    };
+
+    assert_eq!(code, 1);
+    assert_eq!(stdout, "");
+}
+
+#[test]
+fn dumped_json_palette_is_one_document() {
+    let dir = temp_dir("dump");
+    let output = Command::new(env!("CARGO_BIN_EXE_color-picker-ratatui"))
+        .args(["--dump-palette", "--format", "json"])
+        .env("HOME", &dir)
+        .env("XDG_CONFIG_HOME", dir.join("config"))
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_one_json_document(&String::from_utf8(output.stdout).unwrap());
+}